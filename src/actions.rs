@@ -4,14 +4,22 @@ use bevy::prelude::*;
 use bevy_mod_picking::prelude::*;
 use bevy_mod_picking::prelude::Listener;
 use bevy::math::Vec3;
+use bevy_egui::EguiContexts;
 use rand::Rng;
-use crate::{cube::{Cube, CubeInfo, Direction, Movement, RotateAxis, RotateX, RotateY, RotateZ, Face}, settings::Settings};
+use crate::{cube::{Cube, CubeInfo, Puzzle, RotateX, RotateY, RotateZ}, playback::Playback, puzzle::{Direction, LayerRange, Movement, RotateAxis}, recorder::Recorder, settings::{Easing, Settings}};
 
 #[derive(Resource)]
 pub struct ActionStatus {
-    pub angle_to_process: f32,
+    /// Progress of the current action, from 0 to 1.
+    pub progress: f32,
     pub action_queue: VecDeque<Movement>,
     pub cur_action: Option<Movement>,
+    /// Movements on layers parallel to the current action, animated along with it.
+    pub parallel_actions: Vec<Movement>,
+    /// Movements of the last completed action, the current one and its parallel movements.
+    pub last_moves: Vec<Movement>,
+    /// Every completed movement, oldest first.
+    pub history: Vec<Movement>,
     pub drag_start: Option<Vec3>,
     pub drag_end: Option<Vec3>,
     pub selected_entity: Option<Entity>,
@@ -40,11 +48,20 @@ pub fn handle_drag_move(
     rz: Query<&Transform, With<RotateZ>>,
     cubes: Query<&Cube>,
     mut status: ResMut<ActionStatus>,
+    mut playback: ResMut<Playback>,
+    mut recorder: ResMut<Recorder>,
+    keys: Res<ButtonInput<KeyCode>>,
+    touches: Res<Touches>,
+    time: Res<Time>,
     settings: Res<Settings>
 ) {
     if status.computing_solution || !status.action_queue.is_empty() {
         return;
     }
+    // two fingers orbit the camera
+    if touches.iter().count() > 1 {
+        return;
+    }
 
     let c = settings.rotation_trigger_value;
     if !(status.drag_start.is_some() && status.drag_end.is_none()) {
@@ -102,15 +119,27 @@ pub fn handle_drag_move(
         },
     };
 
-    let layer = cubes.get(status.selected_entity.unwrap()).unwrap().coord[axis as usize];
+    let layer = cubes.get(status.selected_entity.unwrap()).unwrap().coord[axis as usize] as u32;
+    // control turns the whole cube, shift turns a wide layer
+    let layers = if keys.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight]) {
+        LayerRange::all(settings.layers)
+    } else if keys.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]) {
+        LayerRange::wide(layer, settings.layers)
+    } else {
+        LayerRange::single(layer)
+    };
 
     let m = Movement {
         axis: axis,
-        layer: layer as u32,
-        direction: dir
+        layers,
+        direction: dir,
+        turns: 1,
     };
     info!("generate movement: {:?}", m);
     status.action_queue.push_back(m);
+    recorder.record(m, time.elapsed_seconds_f64());
+    // the loaded solution does not apply anymore
+    playback.clear();
 
 }
 
@@ -125,152 +154,141 @@ pub fn handle_drag_end(
     status.selected_entity = None;
 }
 
+pub fn handle_keyboard(
+    keys: Res<ButtonInput<KeyCode>>,
+    mut status: ResMut<ActionStatus>,
+    mut playback: ResMut<Playback>,
+    mut recorder: ResMut<Recorder>,
+    time: Res<Time>,
+    settings: Res<Settings>,
+    mut contexts: EguiContexts,
+) {
+    // the keys are typed in a text field
+    if status.computing_solution || contexts.ctx_mut().wants_keyboard_input() {
+        return;
+    }
+
+    let inverse = keys.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]);
+    let bindings = settings.key_bindings;
+    for (key, axis) in [
+        (bindings.rotate_x, RotateAxis::X),
+        (bindings.rotate_y, RotateAxis::Y),
+        (bindings.rotate_z, RotateAxis::Z),
+    ] {
+        if keys.just_pressed(key) {
+            let m = Movement::rotation(axis, inverse, settings.layers);
+            status.action_queue.push_back(m);
+            recorder.record(m, time.elapsed_seconds_f64());
+            // the loaded solution is relative to the previous orientation
+            playback.clear();
+        }
+    }
+}
+
 pub fn frame_handler(
     time: Res<Time>,
     mut query: Query<&mut Transform>,
     mut cubes: Query<&mut Cube>,
     cube_info: Res<CubeInfo>,
     mut status: ResMut<ActionStatus>,
+    mut puzzle: ResMut<Puzzle>,
+    playback: Res<Playback>,
     settings: Res<Settings>
 ) {
 
-    let c = settings.layer_rotation_speed;
+    let c = playback.rotation_speed().unwrap_or(settings.layer_rotation_speed);
 
     if status.action_queue.is_empty() && status.cur_action.is_none() {
         return;
     }
 
-    let movement = if let Some(cur_action) = status.cur_action {
-        cur_action
-    } else {
+    if status.cur_action.is_none() {
         let m = status.action_queue.pop_front().unwrap();
         status.cur_action = Some(m);
-        status.angle_to_process = FRAC_PI_2;
-        m
-    };
+        status.progress = 0.0;
+
+        if settings.overlap_parallel_moves {
+            while let Some(next) = status.action_queue.front().copied() {
+                if !is_parallel(&next, [&m].into_iter().chain(&status.parallel_actions)) {
+                    break;
+                }
+                status.parallel_actions.push(next);
+                status.action_queue.pop_front();
+            }
+        }
+    }
+
+    let movement = status.cur_action.unwrap();
+    let mut movements = vec![movement];
+    movements.extend(status.parallel_actions.iter().copied());
 
     let axis = query.get(match movement.axis {
         RotateAxis::X => cube_info.x.unwrap(),
         RotateAxis::Y => cube_info.y.unwrap(),
         RotateAxis::Z => cube_info.z.unwrap(),
-    }).unwrap();
+    }).unwrap().translation;
 
     let coord_idx = movement.axis as usize;
-    let angle = f32::min(FRAC_PI_2 * time.delta_seconds() * c, status.angle_to_process);
-    status.angle_to_process -= angle;
+    let turns = movements.iter().map(|m| m.turns).max().unwrap();
+    let previous = status.progress;
+    status.progress = if settings.easing == Easing::Instant {
+        1.0
+    } else {
+        f32::min(status.progress + time.delta_seconds() * c / turn_duration(turns), 1.0)
+    };
+    let done = status.progress == 1.0;
 
-    if status.angle_to_process == 0.0 {
+    if done {
         status.cur_action = None;
+        status.parallel_actions.clear();
+        puzzle.apply_all(&movements);
+        status.last_moves = movements.clone();
+        status.history.extend(movements.iter().copied());
     }
 
-    let quat = match movement.direction {
-        Direction::Clockwise => Quat::from_axis_angle(axis.translation, -angle),
-        Direction::CounterClockwise => Quat::from_axis_angle(axis.translation, angle)
-    };
+    for m in movements.iter() {
+        let angle = rotation_angle(settings.easing, previous, status.progress, m.turns);
+        let quat = match m.direction {
+            Direction::Clockwise => Quat::from_axis_angle(axis, -angle),
+            Direction::CounterClockwise => Quat::from_axis_angle(axis, angle)
+        };
 
-    for e in cube_info.cubes.iter() {
-        let mut cube = cubes.get_mut(*e).unwrap();
-        if cube.coord[coord_idx] == movement.layer as i32 {
-            if let Ok(mut cube_transform) = query.get_mut(*e) {
-                cube_transform.rotate_around(Vec3::ZERO, quat);
-            } else {
-                panic!("Something weird happened");
-            }
-            if status.angle_to_process == 0.0 {
-                adjust_coords(&mut cube, &movement, &settings);
-                adjust_faces(&mut cube, &movement);
+        for e in cube_info.cubes.iter() {
+            let mut cube = cubes.get_mut(*e).unwrap();
+            if m.layers.contains(cube.coord[coord_idx]) {
+                if let Ok(mut cube_transform) = query.get_mut(*e) {
+                    cube_transform.rotate_around(Vec3::ZERO, quat);
+                } else {
+                    panic!("Something weird happened");
+                }
+                if done {
+                    cube.coord = m.turn_coord(cube.coord, settings.layers);
+                    cube.sync_colors(&puzzle, &settings);
+                }
             }
         }
     }
 }
 
-fn adjust_coords(cube: &mut Cube, movement: &Movement, settings: &Settings) {
-    // let old = cube.coord;
-    if movement.axis == RotateAxis::X {
-        cube.coord.swap(1, 2);
-        if movement.direction == Direction::Clockwise {
-            cube.coord[2] = settings.layers as i32 - cube.coord[2] - 1;
-        } else if movement.direction == Direction::CounterClockwise {
-            cube.coord[1] = settings.layers as i32 - cube.coord[1] - 1;
-        }
-    } else if movement.axis == RotateAxis::Y {
-        cube.coord.swap(0, 2);
-        if movement.direction == Direction::Clockwise {
-            cube.coord[0] = settings.layers as i32 - cube.coord[0] - 1;
-        } else if movement.direction == Direction::CounterClockwise {
-            cube.coord[2] = settings.layers as i32 - cube.coord[2] - 1;
-        }
-    } else if movement.axis == RotateAxis::Z {
-        cube.coord.swap(0, 1);
-        if movement.direction == Direction::Clockwise {
-            cube.coord[1] = settings.layers as i32 - cube.coord[1] - 1;
-        } else if movement.direction == Direction::CounterClockwise {
-            cube.coord[0] = settings.layers as i32 - cube.coord[0] - 1;
-        }
-    }
+/// Whether `next` turns on the axis of every movement of `group` without sharing a layer with any of them, it can
+/// then be animated along with them.
+pub fn is_parallel<'a>(next: &Movement, group: impl IntoIterator<Item = &'a Movement>) -> bool {
+    group.into_iter().all(|m| m.axis == next.axis && !m.layers.overlaps(&next.layers))
+}
 
-    // info!("{:?} -> {:?}", old, cube.coord);
+/// Duration of a movement of `turns` quarter turns, in quarter turn durations. Each quarter turn past the first
+/// adds half a quarter turn, so a half turn is faster than two quarter turns, as it is by hand.
+fn turn_duration(turns: u32) -> f32 {
+    1.0 + 0.5 * turns.saturating_sub(1) as f32
 }
 
-fn adjust_faces(cube: &mut Cube, movement: &Movement) {
-    let up = *cube.colors.get(&Face::UP).unwrap();
-    let down = *cube.colors.get(&Face::DOWN).unwrap();
-    let front = *cube.colors.get(&Face::FRONT).unwrap();
-    let back = *cube.colors.get(&Face::BACK).unwrap();
-    let left = *cube.colors.get(&Face::LEFT).unwrap();
-    let right = *cube.colors.get(&Face::RIGHT).unwrap();
-    if movement.axis == RotateAxis::X {
-        if movement.direction == Direction::Clockwise {
-            cube.colors.insert(Face::UP, front);
-            cube.colors.insert(Face::DOWN, back);
-            cube.colors.insert(Face::FRONT, down);
-            cube.colors.insert(Face::BACK, up);
-            cube.colors.insert(Face::LEFT, left);
-            cube.colors.insert(Face::RIGHT, right);
-        } else if movement.direction == Direction::CounterClockwise {
-            cube.colors.insert(Face::UP, back);
-            cube.colors.insert(Face::DOWN, front);
-            cube.colors.insert(Face::FRONT, up);
-            cube.colors.insert(Face::BACK, down);
-            cube.colors.insert(Face::LEFT, left);
-            cube.colors.insert(Face::RIGHT, right);
-        }
-    } else if movement.axis == RotateAxis::Y {
-        if movement.direction == Direction::Clockwise {
-            cube.colors.insert(Face::UP, up);
-            cube.colors.insert(Face::DOWN, down);
-            cube.colors.insert(Face::FRONT, right);
-            cube.colors.insert(Face::BACK, left);
-            cube.colors.insert(Face::LEFT, front);
-            cube.colors.insert(Face::RIGHT, back);
-        } else if movement.direction == Direction::CounterClockwise {
-            cube.colors.insert(Face::UP, up);
-            cube.colors.insert(Face::DOWN, down);
-            cube.colors.insert(Face::FRONT, left);
-            cube.colors.insert(Face::BACK, right);
-            cube.colors.insert(Face::LEFT, back);
-            cube.colors.insert(Face::RIGHT, front);
-        }
-    } else if movement.axis == RotateAxis::Z {
-        if movement.direction == Direction::Clockwise {
-            cube.colors.insert(Face::UP, left);
-            cube.colors.insert(Face::DOWN, right);
-            cube.colors.insert(Face::FRONT, front);
-            cube.colors.insert(Face::BACK, back);
-            cube.colors.insert(Face::LEFT, down);
-            cube.colors.insert(Face::RIGHT, up);
-        } else if movement.direction == Direction::CounterClockwise {
-            cube.colors.insert(Face::UP, right);
-            cube.colors.insert(Face::DOWN, left);
-            cube.colors.insert(Face::FRONT, front);
-            cube.colors.insert(Face::BACK, back);
-            cube.colors.insert(Face::LEFT, up);
-            cube.colors.insert(Face::RIGHT, down);
-        }
-    }
+/// Angle turned by a movement of `turns` quarter turns while its progress goes from `from` to `to`.
+fn rotation_angle(easing: Easing, from: f32, to: f32, turns: u32) -> f32 {
+    FRAC_PI_2 * turns as f32 * (easing.apply(to) - easing.apply(from))
 }
 
-pub fn gen_random_movements(steps: u32) -> VecDeque<Movement> {
+/// Random single layer quarter turns of a cube of `layers` layers.
+pub fn gen_random_movements(steps: u32, layers: u32) -> VecDeque<Movement> {
     let mut rng = rand::thread_rng();
     let axis = vec![RotateAxis::X, RotateAxis::Y, RotateAxis::Z];
     let dirs = vec![Direction::Clockwise, Direction::CounterClockwise];
@@ -279,11 +297,60 @@ pub fn gen_random_movements(steps: u32) -> VecDeque<Movement> {
     while cnt < steps {
         let next = Movement {
             axis: axis[rng.gen_range(0..3)],
-            layer: rng.gen_range(0..3),
-            direction: dirs[rng.gen_range(0..2)]
+            layers: LayerRange::single(rng.gen_range(0..layers)),
+            direction: dirs[rng.gen_range(0..2)],
+            turns: 1,
         };
         ret.push_back(next);
         cnt += 1;
     }
     ret
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::notation::notation;
+
+    #[test]
+    fn test_rotation_angle() {
+        for turns in [1, 2] {
+            let full = FRAC_PI_2 * turns as f32;
+            assert_eq!(rotation_angle(Easing::Instant, 0.0, 1.0, turns), full);
+
+            for easing in Easing::ALL {
+                let steps = [0.0, 0.3, 0.6, 1.0];
+                let angle: f32 = steps.windows(2).map(|w| rotation_angle(easing, w[0], w[1], turns)).sum();
+                assert!((angle - full).abs() < 1e-5, "{}", easing.label());
+            }
+        }
+    }
+
+    #[test]
+    fn test_is_parallel() {
+        let r = Movement { axis: RotateAxis::X, layers: LayerRange::single(2), direction: Direction::Clockwise, turns: 1 };
+        let l = Movement { layers: LayerRange::single(0), ..r };
+        let m = Movement { layers: LayerRange::single(1), ..r };
+        assert!(is_parallel(&l, &[r]));
+        assert!(is_parallel(&m, &[r, l]));
+        assert!(!is_parallel(&r, &[Movement { layers: LayerRange { start: 1, end: 2 }, ..r }]));
+        assert!(!is_parallel(&Movement { axis: RotateAxis::Y, ..l }, &[r]));
+    }
+
+    #[test]
+    fn test_turn_duration() {
+        assert_eq!(turn_duration(1), 1.0);
+        assert_eq!(turn_duration(2), 1.5);
+        assert!(turn_duration(3) < 3.0);
+    }
+
+    #[test]
+    fn test_random_movements() {
+        for layers in [2, 3, 5] {
+            let movements = Vec::from(gen_random_movements(100, layers));
+            assert_eq!(movements.len(), 100);
+            assert!(movements.iter().all(|m| m.layers.fits(layers)));
+            assert!(notation(&movements, layers).is_ok());
+        }
+    }
+}
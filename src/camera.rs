@@ -1,13 +1,117 @@
-use bevy::{input::{mouse::{MouseButtonInput, MouseMotion}, ButtonState}, prelude::*, window::CursorGrabMode};
-use crate::settings::Settings;
+use std::f32::consts::{FRAC_PI_2, FRAC_PI_4};
+
+use bevy::{input::{mouse::{MouseButtonInput, MouseMotion, MouseScrollUnit, MouseWheel}, ButtonState}, prelude::*, window::CursorGrabMode};
+use bevy_egui::EguiContexts;
+use crate::{actions::ActionStatus, settings::Settings};
+
+/// Closest and farthest distance of the camera to the cube center.
+const MIN_DISTANCE: f32 = 6.0;
+const MAX_DISTANCE: f32 = 30.0;
+/// The pitch stays away from the poles so the view never flips.
+const MAX_PITCH: f32 = FRAC_PI_2 - 0.01;
+/// Fraction of the orbit velocity kept after one second once the drag stops.
+const DAMPING: f32 = 0.001;
+
+/// Preset views of the camera.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum View {
+    Front,
+    Top,
+    Isometric,
+}
+
+impl View {
+    pub const ALL: [View; 3] = [View::Front, View::Top, View::Isometric];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            View::Front => "front",
+            View::Top => "top",
+            View::Isometric => "isometric",
+        }
+    }
+}
+
+/// Camera orbiting around the cube center, the yaw is measured from the front (+Z) towards the right (+X).
+#[derive(Resource)]
+pub struct OrbitCamera {
+    pub yaw: f32,
+    pub pitch: f32,
+    pub distance: f32,
+    /// Distance the camera is smoothly zoomed to.
+    pub target_distance: f32,
+    /// Angular velocities in radians per second, decaying over time.
+    pub yaw_velocity: f32,
+    pub pitch_velocity: f32,
+}
+
+impl Default for OrbitCamera {
+    fn default() -> Self {
+        let mut camera = OrbitCamera {
+            yaw: 0.0,
+            pitch: 0.0,
+            distance: 0.0,
+            target_distance: 0.0,
+            yaw_velocity: 0.0,
+            pitch_velocity: 0.0,
+        };
+        camera.set_view(View::Isometric);
+        camera
+    }
+}
+
+impl OrbitCamera {
+    pub fn set_view(&mut self, view: View) {
+        let (yaw, pitch) = match view {
+            View::Front => (0.0, 0.0),
+            View::Top => (0.0, MAX_PITCH),
+            // the camera looks at the up, front and right faces equally
+            View::Isometric => (FRAC_PI_4, f32::atan(1.0 / f32::sqrt(2.0))),
+        };
+        self.yaw = yaw;
+        self.pitch = pitch;
+        self.target_distance = f32::sqrt(108.0);
+        self.distance = self.target_distance;
+        self.yaw_velocity = 0.0;
+        self.pitch_velocity = 0.0;
+    }
+
+    pub fn reset(&mut self) {
+        self.set_view(View::Isometric);
+    }
+
+    /// Sets the orbit velocity from a drag of `delta` pixels during the last frame.
+    fn drag(&mut self, delta: Vec2, settings: &Settings, dt: f32) {
+        if delta == Vec2::ZERO || dt <= 0.0 {
+            return;
+        }
+        let c = settings.view_rotation_speed / 2000.0;
+        self.yaw_velocity = delta.x * c / dt;
+        self.pitch_velocity = delta.y * c / dt;
+    }
+
+    /// Scales the target distance by `factor`.
+    fn zoom(&mut self, factor: f32) {
+        self.target_distance = (self.target_distance * factor).clamp(MIN_DISTANCE, MAX_DISTANCE);
+    }
+
+    fn transform(&self) -> Transform {
+        let position = Vec3::new(
+            self.pitch.cos() * self.yaw.sin(),
+            self.pitch.sin(),
+            self.pitch.cos() * self.yaw.cos(),
+        ) * self.distance;
+        Transform::from_translation(position).looking_at(Vec3::ZERO, Vec3::Y)
+    }
+}
 
 pub fn setup_camera(
     mut commands: Commands,
+    orbit: Res<OrbitCamera>,
 ) {
     commands.spawn((
         Camera3dBundle {
-            transform: Transform::from_xyz(6.0, 6.0, 6.0)
-                .looking_at(Vec3::ZERO, Vec3::Y),
+            transform: orbit.transform(),
             ..default()
         },
     ));
@@ -17,14 +121,23 @@ pub fn handle_view(
     mut windows: Query<&mut Window>,
     mut mouse_button_input_events: EventReader<MouseButtonInput>,
     mut mouse_motion_events: EventReader<MouseMotion>,
-    mut camera: Query<&mut Transform, With<Camera>>,
+    mut mouse_wheel_events: EventReader<MouseWheel>,
+    mut orbit: ResMut<OrbitCamera>,
+    time: Res<Time>,
+    touches: Res<Touches>,
     settings: Res<Settings>,
+    mut contexts: EguiContexts,
 ) {
     let mut window = windows.single_mut();
+    // touch devices orbit with two fingers, see `handle_touch`
+    let touching = touches.iter().next().is_some();
+    // the pointer is over a window, scrolling or dragging it
+    let ctx = contexts.ctx_mut();
+    let over_ui = ctx.wants_pointer_input() || ctx.is_pointer_over_area();
 
     for event in mouse_button_input_events.read() {
         if event.button == MouseButton::Right {
-            if event.state == ButtonState::Pressed {
+            if event.state == ButtonState::Pressed && !touching && !over_ui {
                 info!("right button pressed");
                 window.cursor.visible = false;
                 window.cursor.grab_mode = CursorGrabMode::Locked;
@@ -36,32 +149,70 @@ pub fn handle_view(
         }
     }
 
+    let mut delta = Vec2::ZERO;
     for event in mouse_motion_events.read() {
         if window.cursor.grab_mode == CursorGrabMode::Locked {
-            info!("{:?}", event);
-            process_rotation(&mut camera.single_mut(), &event.delta, &settings);
+            delta += event.delta;
         }
     }
-    mouse_motion_events.clear();
+    orbit.drag(delta, &settings, time.delta_seconds());
+
+    for event in mouse_wheel_events.read() {
+        if over_ui {
+            continue;
+        }
+        let lines = match event.unit {
+            MouseScrollUnit::Line => event.y,
+            MouseScrollUnit::Pixel => event.y / 50.0,
+        };
+        orbit.zoom(f32::powf(0.9, lines));
+    }
 }
 
-fn process_rotation(camera: &mut Transform, delta: &Vec2, settings: &Settings) {
-    let c = settings.view_rotation_speed / 4000.0;
-    if delta.x.abs() <= delta.y.abs() {
-        let quat_y = Quat::from_euler(
-            EulerRot::XYZ,
-            -delta.y * c * camera.translation.z,
-            0.0,
-            delta.y * c * camera.translation.x,
-        );
-        camera.rotate_around(Vec3::ZERO, quat_y);
-    } else {
-        let quat_x = Quat::from_euler(
-            EulerRot::XYZ,
-            0.0,
-            delta.x * c,
-            0.0
-        );
-        camera.rotate_around(Vec3::ZERO, quat_x);
+/// Two finger drags orbit the camera and pinches zoom, one finger drags are left to the layer turns.
+pub fn handle_touch(
+    touches: Res<Touches>,
+    mut orbit: ResMut<OrbitCamera>,
+    mut status: ResMut<ActionStatus>,
+    time: Res<Time>,
+    settings: Res<Settings>,
+) {
+    let fingers: Vec<&Touch> = touches.iter().collect();
+    if fingers.len() != 2 {
+        return;
+    }
+
+    // cancel the layer turn started by the first finger
+    status.drag_start = None;
+    status.selected_entity = None;
+
+    let (a, b) = (fingers[0], fingers[1]);
+    orbit.drag((a.delta() + b.delta()) / 2.0, &settings, time.delta_seconds());
+
+    let previous = a.previous_position().distance(b.previous_position());
+    let current = a.position().distance(b.position());
+    if previous > 0.0 && current > 0.0 {
+        orbit.zoom(previous / current);
     }
-}
\ No newline at end of file
+}
+
+/// Moves the camera along its orbit, slowing it down and easing the zoom.
+pub fn update_camera(
+    mut orbit: ResMut<OrbitCamera>,
+    mut camera: Query<&mut Transform, With<Camera>>,
+    time: Res<Time>,
+) {
+    let dt = time.delta_seconds();
+
+    orbit.yaw += orbit.yaw_velocity * dt;
+    orbit.pitch = (orbit.pitch + orbit.pitch_velocity * dt).clamp(-MAX_PITCH, MAX_PITCH);
+
+    let damping = DAMPING.powf(dt);
+    orbit.yaw_velocity *= damping;
+    orbit.pitch_velocity *= damping;
+
+    let t = f32::min(10.0 * dt, 1.0);
+    orbit.distance += (orbit.target_distance - orbit.distance) * t;
+
+    *camera.single_mut() = orbit.transform();
+}
@@ -1,11 +1,11 @@
-use bevy::render::mesh::{Indices, PrimitiveTopology};
+use bevy::render::mesh::{Indices, PrimitiveTopology, VertexAttributeValues};
 use bevy::render::render_asset::RenderAssetUsages;
 use bevy::prelude::*;
 use std::collections::HashMap;
 use bevy_mod_picking::prelude::*;
 use bevy_mod_picking::backends::raycast::RaycastPickable;
 
-use crate::{actions, settings::Settings};
+use crate::{actions, puzzle::{PuzzleState, Side}, settings::Settings};
 
 #[derive(Component)]
 pub struct Rotator;
@@ -19,24 +19,9 @@ pub struct RotateY;
 #[derive(Component)]
 pub struct RotateZ;
 
-#[derive(Clone, Copy, PartialEq, Debug)]
-pub enum Direction {
-    // facing the negative direction
-    Clockwise,
-    CounterClockwise,
-}
-
-#[derive(Clone, Copy, PartialEq, Debug)]
-pub enum RotateAxis {
-    X, Y, Z
-}
-
-#[derive(Clone, Copy, PartialEq, Debug)]
-pub struct Movement {
-    pub axis: RotateAxis,
-    pub layer: u32,
-    pub direction: Direction,
-}
+/// The logical state of the cube, every completed `Movement` is applied to it.
+#[derive(Resource, Deref, DerefMut)]
+pub struct Puzzle(pub PuzzleState);
 
 #[derive(Component, Clone)]
 pub struct Cube {
@@ -106,6 +91,25 @@ impl Cube {
         self.colors.insert(Face::BEVELED, setting.color_beleved);
     }
 
+    /// Sets the colours of the outer faces from the puzzle state, inner faces are black.
+    pub fn sync_colors(&mut self, puzzle: &PuzzleState, settings: &Settings) {
+        for (face, side) in [
+            (Face::UP, Side::U), (Face::DOWN, Side::D), (Face::LEFT, Side::L),
+            (Face::RIGHT, Side::R), (Face::FRONT, Side::F), (Face::BACK, Side::B),
+        ] {
+            let outer = match side {
+                Side::U => self.up_face(settings),
+                Side::D => self.down_dace(settings),
+                Side::L => self.left_face(settings),
+                Side::R => self.right_face(settings),
+                Side::F => self.front_face(settings),
+                Side::B => self.back_face(settings),
+            };
+            let color = if outer { settings.side_color(puzzle.get(self.coord, side)) } else { Color::BLACK };
+            self.colors.insert(face, color);
+        }
+    }
+
     #[allow(unused)]
     fn down_dace(&self, setting: &Settings) -> bool { self.coord[1] == 0 }
     fn up_face(&self, setting: &Settings) -> bool { self.coord[1] as u32 == setting.layers - 1 }
@@ -145,6 +149,7 @@ pub fn setup_cube(
 
     let layers = settings.layers;
     let center = layers as f32 / 2.0;
+    commands.insert_resource(Puzzle(PuzzleState::new(layers)));
 
     for x in 0..layers {
         for y in 0..layers {
@@ -221,6 +226,66 @@ pub fn setup_cube(
 
 }
 
+/// Puts every cube back at its coordinates without rotation and rebuilds its mesh from the puzzle state,
+/// for a state that was not reached by animated movements.
+pub fn reset_cubes(
+    puzzle: &PuzzleState,
+    settings: &Settings,
+    cubes: &mut Query<(&mut Cube, &mut Transform, &Handle<Mesh>)>,
+    meshes: &mut Assets<Mesh>,
+) {
+    let center = settings.layers as f32 / 2.0;
+    for (mut cube, mut transform, mesh) in cubes.iter_mut() {
+        let [x, y, z] = cube.coord;
+        *transform = Transform::from_xyz(
+            x as f32 - center + 0.5,
+            y as f32 - center + 0.5,
+            z as f32 - center + 0.5,
+        );
+        cube.sync_colors(puzzle, settings);
+        meshes.insert(mesh, create_mesh(&cube));
+    }
+}
+
+/// Recolours the stickers and the meshes when the colours of the settings change.
+/// The colour of every sticker is replaced by its counterpart in the new scheme, the meshes being turned with the
+/// cubes. The bevels get the new bevel colour, the black inner faces are kept even when the bevels were black.
+pub fn recolor_cubes(
+    settings: Res<Settings>,
+    puzzle: Res<Puzzle>,
+    mut applied: Local<Option<[Color; 7]>>,
+    mut cubes: Query<(&mut Cube, &Handle<Mesh>)>,
+    mut meshes: ResMut<Assets<Mesh>>,
+) {
+    let scheme = settings.scheme();
+    let old = *applied.get_or_insert(scheme);
+    if old == scheme || !settings.has_distinct_colors() {
+        return;
+    }
+
+    let old_linear = old.map(|c| c.to_linear().to_f32_array());
+    for (mut cube, mesh) in cubes.iter_mut() {
+        cube.sync_colors(&puzzle, &settings);
+        cube.colors.insert(Face::BEVELED, settings.color_beleved);
+
+        let vertex_colors = meshes.get_mut(mesh).and_then(|m| m.attribute_mut(Mesh::ATTRIBUTE_COLOR));
+        if let Some(VertexAttributeValues::Float32x4(vertex_colors)) = vertex_colors {
+            let (faces, bevels) = vertex_colors.split_at_mut(FACE_VERTICES);
+            for color in faces {
+                if let Some(i) = old_linear[..6].iter().position(|c| c == color) {
+                    *color = scheme[i].to_linear().to_f32_array();
+                }
+            }
+            bevels.fill(settings.color_beleved.to_linear().to_f32_array());
+        }
+    }
+
+    *applied = Some(scheme);
+}
+
+/// Number of vertices of the six faces of a cube mesh, the vertices of the bevels follow them.
+const FACE_VERTICES: usize = 24;
+
 fn create_mesh(cube: &Cube) -> Mesh {
 
     let c = (1.0 - cube.gap) / 2.0;
@@ -341,6 +406,7 @@ fn create_mesh(cube: &Cube) -> Mesh {
         20,21,23 , 21,22,23, // forward (-z)
     ];
 
+    debug_assert_eq!(vertices.len(), FACE_VERTICES);
     let beveled_corners: Vec<[u32; 3]> = vec![
         [0, 21, 15], // 24 25 26
         [1, 11, 22], // 27 28 29
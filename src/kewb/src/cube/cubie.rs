@@ -135,6 +135,9 @@ impl CubieCube {
             B => B_MOVE,
             B2 => B_MOVE * B_MOVE,
             B3 => B_MOVE * B_MOVE * B_MOVE,
+            M => M_MOVE,
+            M2 => M_MOVE * M_MOVE,
+            M3 => M_MOVE * M_MOVE * M_MOVE,
         };
 
         self * move_state
@@ -200,7 +203,7 @@ impl CubieCube {
         let c_twist = self.count_corner_twist();
         let e_twist = self.count_edge_twist();
         let has_even_permutation = c_perm % 2 == e_perm % 2;
-        let has_valid_twist = c_twist % 3 == 0 && e_twist % 2 == 0;
+        let has_valid_twist = c_twist.is_multiple_of(3) && e_twist.is_multiple_of(2);
 
         has_even_permutation && has_valid_twist
     }
@@ -270,8 +273,10 @@ impl TryFrom<&FaceCube> for CubieCube {
             }
         }
 
-        if !state.is_solvable() {
+        if has_duplicates(&state.cp) || has_duplicates(&state.ep) {
             Err(Error::InvalidFaceletValue)
+        } else if !state.is_solvable() {
+            Err(Error::UnsolvableCube)
         } else {
             Ok(state)
         }
@@ -281,6 +286,7 @@ impl TryFrom<&FaceCube> for CubieCube {
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::cube::scramble::scramble_from_str;
 
     #[test]
     fn test_mult() {
@@ -314,6 +320,47 @@ mod test {
         assert_eq!(state, SOLVED_CUBIE_CUBE);
     }
 
+    #[test]
+    fn test_unsolvable() {
+        let from_str = |s: &str| CubieCube::try_from(&FaceCube::try_from(s).unwrap());
+
+        // flipped UR edge
+        let flipped = "UUUUURUUURURRRRRRRFFFFFFFFFDDDDDDDDDLLLLLLLLLBBBBBBBBB";
+        assert!(matches!(from_str(flipped), Err(Error::UnsolvableCube)));
+
+        // UF edge with the colours of UR
+        let duplicated = "UUUUUUUUURRRRRRRRRFRFFFFFFFDDDDDDDDDLLLLLLLLLBBBBBBBBB";
+        assert!(matches!(from_str(duplicated), Err(Error::InvalidFaceletValue)));
+    }
+
+    #[test]
+    fn test_slice_move() {
+        // M2 U M2 U2 M2 U M2, the H permutation
+        let moves = vec![M2, U, M2, U2, M2, U, M2];
+        let state = CubieCube::default().apply_moves(&moves);
+
+        let expected = CubieCube {
+            ep: [BL, BR, FR, FL, UF, UL, UB, UR, DF, DR, DB, DL],
+            ..SOLVED_CUBIE_CUBE
+        };
+
+        assert_eq!(state, expected);
+        assert_eq!(
+            CubieCube::default().apply_moves(&[M, M, M, M]),
+            SOLVED_CUBIE_CUBE
+        );
+
+        // slice moves read back from their notation, but not in scrambles
+        for m in [M, M2, M3] {
+            assert_eq!(m.to_string().parse::<Move>().unwrap(), m);
+        }
+        assert!(matches!(
+            scramble_from_str("R M U"),
+            Err(Error::InvalidScramble)
+        ));
+        assert!(matches!(scramble_from_str("M2"), Err(Error::InvalidScramble)));
+    }
+
     #[test]
     fn test_scramble() {
         // U F' D' F2 D B2 D' R2 U' F2 R2 D2 R2 U' L B L R F' D B'
@@ -190,6 +190,76 @@ pub fn index_to_eo_cross(index: u16) -> [u8; 12] {
     eo
 }
 
+/// D layer cross edges, in the order of the cross coordinate.
+const CROSS_EDGES: [Edge; 4] = [Edge::DL, Edge::DB, Edge::DR, Edge::DF];
+
+/// Index of the positions and orientations of the cross edges, 0 when the cross is solved.
+pub fn cross_to_index(ep: &[Edge; 12], eo: &[u8; 12]) -> u32 {
+    cross_edges_to_index(CROSS_EDGES.map(|edge| {
+        let pos = ep.iter().position(|&e| e == edge).unwrap();
+        pos as u8 * 2 + eo[pos]
+    }))
+}
+
+/// Index of the states (`position * 2 + orientation`) of the cross edges, in the order DL, DB, DR, DF.
+/// Each edge is numbered among the positions left by the previous ones, counting down from DL.
+pub fn cross_edges_to_index(edges: [u8; 4]) -> u32 {
+    let mut used = [false; 12];
+    let mut perm = 0;
+    let mut orientation = 0;
+
+    for (i, state) in edges.iter().enumerate() {
+        let pos = (state / 2) as usize;
+        let rank = (pos + 1..12).filter(|&p| !used[p]).count() as u32;
+
+        used[pos] = true;
+        perm = perm * (12 - i as u32) + rank;
+        orientation = orientation * 2 + (state % 2) as u32;
+    }
+
+    perm * 16 + orientation
+}
+
+/// States (`position * 2 + orientation`) of the cross edges of an index, in the order DL, DB, DR, DF.
+/// Inverse of `cross_edges_to_index`.
+pub fn index_to_cross_edges(index: u32) -> [u8; 4] {
+    let mut ranks = [0; 4];
+    let mut perm = index / 16;
+
+    for i in (0..4).rev() {
+        ranks[i] = (perm % (12 - i as u32)) as usize;
+        perm /= 12 - i as u32;
+    }
+
+    let mut used = [false; 12];
+    let mut edges = [0; 4];
+
+    for i in 0..4 {
+        let pos = (0..12).rev().filter(|&p| !used[p]).nth(ranks[i]).unwrap();
+        used[pos] = true;
+        edges[i] = pos as u8 * 2 + ((index >> (3 - i)) & 1) as u8;
+    }
+
+    edges
+}
+
+/// Places the cross edges of a cross index, the other edges filling the remaining positions in order.
+pub fn index_to_cross(index: u32) -> ([Edge; 12], [u8; 12]) {
+    let mut ep = [None; 12];
+    let mut eo = [0; 12];
+
+    for (edge, state) in CROSS_EDGES.iter().zip(index_to_cross_edges(index)) {
+        ep[(state / 2) as usize] = Some(*edge);
+        eo[(state / 2) as usize] = state % 2;
+    }
+
+    let mut others = (0..12u8)
+        .map(|e| Edge::try_from(e).unwrap())
+        .filter(|e| !CROSS_EDGES.contains(e));
+
+    (ep.map(|e| e.or_else(|| others.next()).unwrap()), eo)
+}
+
 pub fn index_to_cp_f2l(index: u16) -> [Corner; 8] {
     let mut cp: [u8; 8] = [0, 0, 0, 0, 4, 5, 6, 7];
 
@@ -221,7 +291,10 @@ pub fn index_to_eo_f2l(index: u16) -> [u8; 12] {
 #[cfg(test)]
 mod test {
     use super::*;
-    use crate::cube::cubie::{Edge::*, SOLVED_CUBIE_CUBE};
+    use crate::cube::{
+        constants::CROSS_COUNT,
+        cubie::{Edge::*, SOLVED_CUBIE_CUBE},
+    };
 
     #[test]
     fn test_co_to_index() {
@@ -277,6 +350,21 @@ mod test {
         assert_eq!(&ud_ep[4..12], &edges[4..12]);
     }
 
+    #[test]
+    fn test_cross() {
+        assert_eq!(cross_to_index(&SOLVED_CUBIE_CUBE.ep, &SOLVED_CUBIE_CUBE.eo), 0);
+        assert_eq!(index_to_cross(0), (SOLVED_CUBIE_CUBE.ep, SOLVED_CUBIE_CUBE.eo));
+
+        for index in (0..CROSS_COUNT).step_by(97) {
+            let (ep, eo) = index_to_cross(index);
+            assert_eq!(cross_to_index(&ep, &eo), index);
+        }
+
+        let (ep, eo) = index_to_cross(CROSS_COUNT - 1);
+        assert_eq!(ep[..4], [DL, DB, DR, DF]);
+        assert_eq!(eo[..4], [1, 1, 1, 1]);
+    }
+
     #[test]
     fn test_e_ep() {
         assert_eq!(e_ep_to_index(&SOLVED_CUBIE_CUBE.ep), 0);
@@ -5,8 +5,11 @@ use crate::error::Error;
 use self::Move::*;
 use super::cubie::{Corner::*, CubieCube, Edge::*};
 
-/// Layer moves, Up, Down, Right, Left, Face, Back.
+/// Layer moves, Up, Down, Right, Left, Face, Back, and the Middle slice turning like L.
 /// $ clockwise, $2 double, $3 counter-clockwise.
+/// M moves the slice edges but not the centers, which the cubie level doesn't have, so an odd number of them leaves
+/// an unsolvable `CubieCube`. They are only made by the Roux solver, which tracks the centers, and are parsed back
+/// from their notation but rejected in scrambles, see `scramble_from_str`.
 #[rustfmt::skip]
 #[allow(clippy::upper_case_acronyms)]
 #[derive(Debug, PartialEq, Clone, Copy)]
@@ -17,6 +20,7 @@ pub enum Move {
     L, L2, L3,
     F, F2, F3,
     B, B2, B3,
+    M, M2, M3,
 }
 
 impl fmt::Display for Move {
@@ -28,6 +32,7 @@ impl fmt::Display for Move {
             L3 => write!(f, "L'"),
             F3 => write!(f, "F'"),
             B3 => write!(f, "B'"),
+            M3 => write!(f, "M'"),
             _ => write!(f, "{:?}", self),
         }
     }
@@ -56,6 +61,9 @@ impl FromStr for Move {
             "B" => Ok(B),
             "B'" => Ok(B3),
             "B2" => Ok(B2),
+            "M" => Ok(M),
+            "M'" => Ok(M3),
+            "M2" => Ok(M2),
             _ => Err(Error::InvalidScramble),
         }
     }
@@ -68,7 +76,8 @@ impl Move {
             (&self, other),
             (U | U2 | U3, D | D2 | D3) 
             | (R | R2 | R3, L | L2 | L3) 
-            | (F | F2 | F3, B | B2 | B3),
+            | (F | F2 | F3, B | B2 | B3)
+            | (R | R2 | R3 | L | L2 | L3, M | M2 | M3),
         )
     }
 
@@ -81,6 +90,7 @@ impl Move {
             | (L | L2 | L3, L | L2 | L3)
             | (F | F2 | F3, F | F2 | F3)
             | (B | B2 | B3, B | B2 | B3)
+            | (M | M2 | M3, M | M2 | M3)
         )
     }
 
@@ -98,6 +108,8 @@ impl Move {
             F3 => F,
             B => B3,
             B3 => B,
+            M => M3,
+            M3 => M,
             _ => self,
         }
     }
@@ -148,3 +160,11 @@ pub const B_MOVE: CubieCube = CubieCube {
     ep: [UB, DB, FR, FL, BR, UR, UF, UL, DF, DR, BL, DL],
     eo: [1, 1, 0, 0, 1, 0, 0, 0, 0, 0, 1, 0],
 };
+
+/// The centers are not part of the cubie level, so only the edges move.
+pub const M_MOVE: CubieCube = CubieCube {
+    cp: [UBL, UBR, UFR, UFL, DFL, DFR, DBR, DBL],
+    co: [0, 0, 0, 0, 0, 0, 0, 0],
+    ep: [BL, BR, FR, FL, DB, UR, UB, UL, UF, DR, DF, DL],
+    eo: [0, 0, 0, 0, 1, 0, 1, 0, 1, 0, 1, 0],
+};
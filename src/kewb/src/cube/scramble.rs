@@ -2,9 +2,13 @@ use std::str::FromStr;
 
 use crate::{error::Error, CubieCube, Move, Solver};
 
+/// Parses a scramble of face turns. Slice moves are rejected, an odd number of them can't be solved, see `Move`.
 pub fn scramble_from_str(s: &str) -> Result<Vec<Move>, Error> {
     s.split_whitespace()
-        .map(|word| Move::from_str(word.trim()))
+        .map(|word| match Move::from_str(word.trim())? {
+            Move::M | Move::M2 | Move::M3 => Err(Error::InvalidScramble),
+            m => Ok(m),
+        })
         .collect()
 }
 
@@ -18,6 +18,12 @@ pub enum Error {
     InvalidFaceletValue,
     #[error("Invalid cubie reperesentation")]
     InvalidCubieValue,
+    #[error("Unsolvable cube")]
+    UnsolvableCube,
+    #[error("Incompatible table file: {0}")]
+    IncompatibleTable(String),
+    #[error("Corrupted table file")]
+    CorruptedTable,
     #[error("{0}")]
     IOError(#[from] io::Error),
     #[error("{0}")]
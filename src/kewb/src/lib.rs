@@ -1,11 +1,13 @@
 //! A crate for manipulating and solving the 3x3 Rubik's cube with [Kociemba's two phase algorithm](http://kociemba.org/cube.htm).
 
 pub(crate) mod cube;
+pub(crate) mod methods;
 pub(crate) mod two_phase;
 
 pub use cube::{cubie::CubieCube, facelet::Color, facelet::FaceCube, moves::Move};
+pub use methods::{Stage, StagedSolution};
 pub use two_phase::solver::{Solution, Solver};
-pub use two_phase::utils::DataTable;
+pub use two_phase::utils::{DataTable, Table};
 
 /// Module containing 3x3 cube constants.
 pub mod constants {
@@ -42,4 +44,29 @@ pub mod scramble {
     pub use crate::cube::scramble::*;
 }
 
+/// Module for recognizing OLL and PLL cases and looking up their algorithms.
+pub mod last_layer {
+    pub use crate::methods::last_layer::*;
+}
+
+/// Module containing the piece search used by the human method solvers.
+pub mod search {
+    pub use crate::methods::search::*;
+}
+
+/// Module containing the layer by layer beginner method solver.
+pub mod beginner {
+    pub use crate::methods::beginner::*;
+}
+
+/// Module containing the CFOP method solver.
+pub mod cfop {
+    pub use crate::methods::cfop::*;
+}
+
+/// Module containing the Roux method solver.
+pub mod roux {
+    pub use crate::methods::roux::*;
+}
+
 pub mod error;
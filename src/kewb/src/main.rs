@@ -0,0 +1,238 @@
+//! Command-line solver, solves facelet strings and scrambles with the two phase algorithm.
+
+use std::{
+    env, fs, process,
+    time::{Duration, Instant},
+};
+
+use kewb::{
+    error::Error,
+    fs::{read_table, save_table, verify_table},
+    scramble::scramble_from_str,
+    CubieCube, DataTable, FaceCube, Solver,
+};
+
+const USAGE: &str = "\
+Usage: kewb [OPTIONS] [CUBE]...
+       kewb generate <PATH>
+       kewb verify <PATH>
+
+Solves each CUBE, a facelet string (UUUUUUUUURRRRRRRRRFFFFFFFFFDDDDDDDDDLLLLLLLLLBBBBBBBBB when solved)
+or a scramble (\"R U R' U'\").
+
+Options:
+  -f, --file <PATH>        solve the cubes of a file, one per line, lines starting with # are skipped
+  -t, --table <PATH>       read the tables written by kewb::fs::write_table instead of generating them
+  -n, --max-length <N>     only look for solutions shorter than N moves [default: 23]
+      --timeout <SECONDS>  stop searching after SECONDS, keeping the best solution found
+  -o, --optimal            keep searching until the shortest solution is found
+  -h, --help               print this help
+
+generate writes the tables to PATH, verify checks the tables of PATH can be read.";
+
+struct Options {
+    cubes: Vec<String>,
+    table: Option<String>,
+    max_length: u8,
+    timeout: Option<Duration>,
+    optimal: bool,
+}
+
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<Options, String> {
+    let mut options = Options {
+        cubes: vec![],
+        table: None,
+        max_length: 23,
+        timeout: None,
+        optimal: false,
+    };
+
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or(format!("missing value for {arg}"));
+
+        match arg.as_str() {
+            "-f" | "--file" => {
+                let path = value()?;
+                let text = fs::read_to_string(&path).map_err(|e| format!("{path}: {e}"))?;
+                let lines = text.lines().map(str::trim);
+                let cubes = lines.filter(|line| !line.is_empty() && !line.starts_with('#'));
+
+                options.cubes.extend(cubes.map(String::from));
+            }
+            "-t" | "--table" => options.table = Some(value()?),
+            "-n" | "--max-length" => {
+                let n = value()?;
+                options.max_length = n.parse().map_err(|_| format!("invalid length {n}"))?;
+            }
+            "--timeout" => {
+                let seconds = value()?;
+                let timeout = seconds.parse().ok().and_then(|s| Duration::try_from_secs_f64(s).ok());
+
+                options.timeout = Some(timeout.ok_or(format!("invalid timeout {seconds}"))?);
+            }
+            "-o" | "--optimal" => options.optimal = true,
+            "-h" | "--help" => {
+                println!("{USAGE}");
+                process::exit(0);
+            }
+            _ if arg.starts_with('-') && arg.len() > 1 => return Err(format!("unknown option {arg}")),
+            _ => options.cubes.push(arg),
+        }
+    }
+
+    Ok(options)
+}
+
+/// Reads a facelet string, or else a scramble applied to the solved cube.
+fn parse_cube(cube: &str) -> Result<CubieCube, Error> {
+    if cube.len() == 54 && !cube.contains(char::is_whitespace) {
+        return CubieCube::try_from(&FaceCube::try_from(cube)?);
+    }
+
+    Ok(CubieCube::from(&scramble_from_str(cube)?))
+}
+
+/// Names, number of entries and size in bytes of the tables.
+fn table_sizes(table: &DataTable) -> Vec<(&'static str, usize, usize)> {
+    let moves = &table.move_table;
+    let pruning = &table.pruning_table;
+    let u16_tables = [
+        ("co moves", &moves.co),
+        ("eo moves", &moves.eo),
+        ("e combo moves", &moves.e_combo),
+        ("cp moves", &moves.cp),
+        ("ep moves", &moves.ep),
+        ("e ep moves", &moves.e_ep),
+    ];
+    let u8_tables = [
+        ("co e pruning", &pruning.co_e),
+        ("eo e pruning", &pruning.eo_e),
+        ("cp e pruning", &pruning.cp_e),
+        ("ep e pruning", &pruning.ep_e),
+    ];
+
+    let mut sizes: Vec<_> = u16_tables.iter().map(|(name, t)| (*name, t.as_slice().len(), t.as_slice().len() * 2)).collect();
+    sizes.extend(u8_tables.iter().map(|(name, t)| (*name, t.as_slice().len(), t.as_slice().len())));
+    sizes
+}
+
+fn print_sizes(table: &DataTable) {
+    for (name, entries, bytes) in table_sizes(table) {
+        println!("  {name:<14} {entries:>9} entries {:>9.1} KiB", bytes as f64 / 1024.0);
+    }
+}
+
+fn generate(path: &str) -> Result<(), Error> {
+    let start = Instant::now();
+    let table = DataTable::default();
+    println!("generated the tables in {:.2}s", start.elapsed().as_secs_f64());
+    print_sizes(&table);
+
+    let start = Instant::now();
+    save_table(&table, path)?;
+    println!(
+        "wrote {path}, {:.1} KiB, in {:.2}s",
+        fs::metadata(path)?.len() as f64 / 1024.0,
+        start.elapsed().as_secs_f64()
+    );
+
+    Ok(())
+}
+
+fn verify(path: &str) -> Result<(), Error> {
+    let start = Instant::now();
+    let table = verify_table(path)?;
+    println!("read {path} in {:.2}s", start.elapsed().as_secs_f64());
+    print_sizes(&table);
+
+    Ok(())
+}
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+
+    if let Some(command) = args.first().filter(|c| matches!(c.as_str(), "generate" | "verify")) {
+        let [_, path] = args.as_slice() else {
+            eprintln!("{command} takes a single PATH\n\n{USAGE}");
+            process::exit(2);
+        };
+
+        let done = match command.as_str() {
+            "generate" => generate(path),
+            _ => verify(path),
+        };
+
+        if let Err(e) = done {
+            eprintln!("{path}: {e}");
+            process::exit(1);
+        }
+        return;
+    }
+
+    let options = parse_args(args.into_iter()).unwrap_or_else(|e| {
+        eprintln!("{e}\n\n{USAGE}");
+        process::exit(2);
+    });
+
+    if options.cubes.is_empty() {
+        eprintln!("{USAGE}");
+        process::exit(2);
+    }
+
+    let start = Instant::now();
+    let table = match &options.table {
+        Some(path) => read_table(path).unwrap_or_else(|e| {
+            eprintln!("{path}: {e}");
+            process::exit(1);
+        }),
+        None => DataTable::default(),
+    };
+    eprintln!("tables ready in {:.2}s", start.elapsed().as_secs_f64());
+
+    let mut failed = false;
+
+    for cube in &options.cubes {
+        println!("{cube}");
+
+        let state = match parse_cube(cube) {
+            Ok(state) => state,
+            Err(e) => {
+                println!("  {e}");
+                failed = true;
+                continue;
+            }
+        };
+
+        let mut solver = Solver::new(&table, options.max_length).with_optimal(options.optimal);
+        if let Some(timeout) = options.timeout {
+            solver = solver.with_timeout(timeout);
+        }
+
+        let start = Instant::now();
+        let solution = solver.solve(state);
+        let time = start.elapsed().as_secs_f64();
+
+        match solution {
+            Some(solution) => {
+                let note = match (options.optimal, solver.is_timed_out()) {
+                    (_, true) => ", best found before the timeout",
+                    (true, false) => ", optimal",
+                    (false, false) => "",
+                };
+                println!("  {solution} ({} moves, {time:.3}s{note})", solution.len());
+            }
+            None if solver.is_timed_out() => {
+                println!("  no solution found before the timeout ({time:.3}s)");
+                failed = true;
+            }
+            None => {
+                println!("  no solution shorter than {} moves ({time:.3}s)", options.max_length);
+                failed = true;
+            }
+        }
+    }
+
+    if failed {
+        process::exit(1);
+    }
+}
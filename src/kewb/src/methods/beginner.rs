@@ -0,0 +1,244 @@
+use super::{
+    last_layer::{recognize_oll, recognize_pll},
+    search::{Piece, PieceSearch},
+    utils::{push_moves, rotate_moves},
+    Stage, StagedSolution,
+};
+use crate::{
+    cube::{
+        cubie::{Corner, CubieCube, Edge},
+        moves::Move::{self, *},
+    },
+    error::Error,
+};
+
+/// Cross edges, solved one at a time.
+const CROSS_EDGES: [Edge; 4] = [Edge::DF, Edge::DR, Edge::DB, Edge::DL];
+/// The four slots in the order front-right, front-left, back-left, back-right,
+/// matching the relabeling done by `rotate_moves`.
+const SLOT_CORNERS: [Corner; 4] = [Corner::DFR, Corner::DFL, Corner::DBL, Corner::DBR];
+const SLOT_EDGES: [Edge; 4] = [Edge::FR, Edge::FL, Edge::BL, Edge::BR];
+/// U layer corner position above each slot.
+const ABOVE_SLOT: [Corner; 4] = [Corner::UFR, Corner::UFL, Corner::UBL, Corner::UBR];
+
+const FACE_MOVES: [Move; 18] = [
+    U, U2, U3, D, D2, D3, R, R2, R3, L, L2, L3, F, F2, F3, B, B2, B3,
+];
+const AUFS: [Option<Move>; 4] = [None, Some(U), Some(U2), Some(U3)];
+
+/// R U R' U', written for the front-right slot.
+const SEXY_MOVE: [Move; 4] = [R, U, R3, U3];
+/// Inserts the edge at UF into the front-right slot.
+const RIGHT_INSERT: [Move; 8] = [U, R, U3, R3, U3, F3, U, F];
+/// Inserts the edge at UR into the front-right slot.
+const LEFT_INSERT: [Move; 8] = [U3, F3, U, F, U, R, U3, R3];
+/// F R U R' U' F', flips last layer edges.
+const EDGE_FLIP: [Move; 6] = [F, R, U, R3, U3, F3];
+
+fn is_edge_solved(state: &CubieCube, edge: Edge) -> bool {
+    state.ep[edge as usize] == edge && state.eo[edge as usize] == 0
+}
+
+fn is_corner_solved(state: &CubieCube, corner: Corner) -> bool {
+    state.cp[corner as usize] == corner && state.co[corner as usize] == 0
+}
+
+fn corner_position(state: &CubieCube, corner: Corner) -> usize {
+    state.cp.iter().position(|&c| c == corner).unwrap()
+}
+
+fn edge_position(state: &CubieCube, edge: Edge) -> usize {
+    state.ep.iter().position(|&e| e == edge).unwrap()
+}
+
+/// Applies `moves` to the state and records them in the stage.
+fn play(state: &mut CubieCube, stage: &mut Vec<Move>, moves: &[Move]) {
+    *state = state.apply_moves(moves);
+    push_moves(stage, moves);
+}
+
+fn solve_cross(state: &mut CubieCube) -> Vec<Move> {
+    let mut moves = vec![];
+    let mut pieces = vec![];
+
+    for edge in CROSS_EDGES {
+        pieces.push(Piece::Edge(edge));
+        let group: Vec<usize> = (0..pieces.len()).collect();
+        let search = PieceSearch::new(pieces.clone(), &FACE_MOVES, &[group]);
+        let found = search.solve(state, 8).unwrap();
+
+        play(state, &mut moves, &found);
+    }
+
+    moves
+}
+
+fn solve_first_layer_corners(state: &mut CubieCube) -> Vec<Move> {
+    let mut moves = vec![];
+
+    for (slot, &corner) in SLOT_CORNERS.iter().enumerate() {
+        if is_corner_solved(state, corner) {
+            continue;
+        }
+
+        // take the corner out of the D layer
+        let pos = corner_position(state, corner);
+        if let Some(other) = SLOT_CORNERS.iter().position(|&c| c as usize == pos) {
+            play(state, &mut moves, &rotate_moves(&SEXY_MOVE, other));
+        }
+
+        // bring it above its slot
+        while corner_position(state, corner) != ABOVE_SLOT[slot] as usize {
+            play(state, &mut moves, &[U]);
+        }
+
+        let sexy_move = rotate_moves(&SEXY_MOVE, slot);
+        while !is_corner_solved(state, corner) {
+            play(state, &mut moves, &sexy_move);
+        }
+    }
+
+    moves
+}
+
+fn solve_second_layer(state: &mut CubieCube) -> Vec<Move> {
+    let mut moves = vec![];
+    let mut solved: Vec<Edge> = vec![];
+
+    for (slot, &edge) in SLOT_EDGES.iter().enumerate() {
+        if !is_edge_solved(state, edge) {
+            // take the edge out of the middle layer
+            let pos = edge_position(state, edge);
+            if let Some(other) = SLOT_EDGES.iter().position(|&e| e as usize == pos) {
+                play(state, &mut moves, &rotate_moves(&RIGHT_INSERT, other));
+            }
+
+            let inserts = [
+                rotate_moves(&RIGHT_INSERT, slot),
+                rotate_moves(&LEFT_INSERT, slot),
+            ];
+            let (auf, insert) = AUFS
+                .iter()
+                .flat_map(|auf| inserts.iter().map(move |insert| (auf, insert)))
+                .find(|(auf, insert)| {
+                    let mut candidate = *state;
+                    if let Some(m) = auf {
+                        candidate = candidate.apply_move(*m);
+                    }
+                    let candidate = candidate.apply_moves(insert);
+
+                    is_edge_solved(&candidate, edge)
+                        && solved.iter().all(|&e| is_edge_solved(&candidate, e))
+                })
+                .unwrap();
+
+            if let Some(m) = auf {
+                play(state, &mut moves, &[*m]);
+            }
+            play(state, &mut moves, insert);
+        }
+
+        solved.push(edge);
+    }
+
+    moves
+}
+
+fn solve_yellow_cross(state: &mut CubieCube) -> Vec<Move> {
+    let is_cross_oriented = |state: &CubieCube| state.eo[4..8].iter().all(|&eo| eo == 0);
+    // at most three edge flips are needed: dot, L shape, line
+    let mut candidates: Vec<Vec<Move>> = vec![vec![]];
+
+    loop {
+        if let Some(moves) = candidates
+            .iter()
+            .find(|moves| is_cross_oriented(&state.apply_moves(moves)))
+        {
+            let moves = moves.clone();
+            *state = state.apply_moves(&moves);
+            return moves;
+        }
+
+        candidates = candidates
+            .iter()
+            .flat_map(|moves| {
+                AUFS.iter().map(move |auf| {
+                    let mut next = moves.clone();
+                    next.extend(auf);
+                    push_moves(&mut next, &EDGE_FLIP);
+                    next
+                })
+            })
+            .collect();
+    }
+}
+
+/// Solves the cube with a layer by layer beginner method.
+/// Stages are cross, first layer corners, second layer, yellow cross, OLL and PLL.
+pub fn solve(state: CubieCube) -> Result<StagedSolution, Error> {
+    if !state.is_solvable() {
+        return Err(Error::InvalidCubieValue);
+    }
+
+    let mut state = state;
+    let mut stages = vec![];
+
+    let moves = solve_cross(&mut state);
+    stages.push(Stage { name: "Cross", moves });
+
+    let moves = solve_first_layer_corners(&mut state);
+    stages.push(Stage { name: "First layer corners", moves });
+
+    let moves = solve_second_layer(&mut state);
+    stages.push(Stage { name: "Second layer", moves });
+
+    let moves = solve_yellow_cross(&mut state);
+    stages.push(Stage { name: "Yellow cross", moves });
+
+    let oll = recognize_oll(&state).ok_or(Error::InvalidCubieValue)?;
+    let mut moves = vec![];
+    play(&mut state, &mut moves, &oll.get_all_moves());
+    stages.push(Stage { name: "OLL", moves });
+
+    let pll = recognize_pll(&state).ok_or(Error::InvalidCubieValue)?;
+    let mut moves = vec![];
+    play(&mut state, &mut moves, &pll.get_all_moves());
+    stages.push(Stage { name: "PLL", moves });
+
+    Ok(StagedSolution { stages })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::cube::{cubie::SOLVED_CUBIE_CUBE, generators::generate_random_state};
+
+    #[test]
+    fn test_solve() {
+        for _ in 0..10 {
+            let state = generate_random_state();
+            let solution = solve(state).unwrap();
+            let names: Vec<_> = solution.stages.iter().map(|s| s.name).collect();
+
+            assert_eq!(
+                names,
+                ["Cross", "First layer corners", "Second layer", "Yellow cross", "OLL", "PLL"]
+            );
+
+            let cross = state.apply_moves(&solution.stages[0].moves);
+            assert!(CROSS_EDGES.iter().all(|&e| is_edge_solved(&cross, e)));
+
+            let first_layer = cross.apply_moves(&solution.stages[1].moves);
+            assert!(SLOT_CORNERS.iter().all(|&c| is_corner_solved(&first_layer, c)));
+
+            assert_eq!(state.apply_moves(&solution.get_all_moves()), SOLVED_CUBIE_CUBE);
+        }
+    }
+
+    #[test]
+    fn test_solved_state() {
+        let solution = solve(SOLVED_CUBIE_CUBE).unwrap();
+
+        assert!(solution.is_empty());
+    }
+}
@@ -0,0 +1,241 @@
+use std::{collections::VecDeque, sync::OnceLock};
+
+use super::{
+    last_layer::{recognize_oll, recognize_pll},
+    search::{Piece, PieceSearch},
+    Stage, StagedSolution,
+};
+use crate::{
+    cube::{
+        constants::CROSS_COUNT,
+        cubie::{Corner, CubieCube, Edge, SOLVED_CUBIE_CUBE},
+        index::{cross_edges_to_index, cross_to_index, index_to_cross_edges},
+        moves::Move::{self, *},
+    },
+    error::Error,
+};
+
+const CROSS_EDGES: [Edge; 4] = [Edge::DF, Edge::DR, Edge::DB, Edge::DL];
+/// Corner and edge of each first two layers slot.
+const F2L_PAIRS: [(Corner, Edge); 4] = [
+    (Corner::DFR, Edge::FR),
+    (Corner::DFL, Edge::FL),
+    (Corner::DBL, Edge::BL),
+    (Corner::DBR, Edge::BR),
+];
+
+const CROSS_MOVES: [Move; 18] = [
+    U, U2, U3, D, D2, D3, R, R2, R3, L, L2, L3, F, F2, F3, B, B2, B3,
+];
+/// F2L pairs are inserted without D moves.
+const F2L_MOVES: [Move; 15] = [U, U2, U3, R, R2, R3, L, L2, L3, F, F2, F3, B, B2, B3];
+
+const PAIR_MAX_LENGTH: u8 = 14;
+
+/// Checks if the D layer cross is solved.
+pub fn is_cross_solved(state: &CubieCube) -> bool {
+    CROSS_EDGES
+        .iter()
+        .all(|&e| state.ep[e as usize] == e && state.eo[e as usize] == 0)
+}
+
+fn is_pair_solved(state: &CubieCube, (corner, edge): (Corner, Edge)) -> bool {
+    state.cp[corner as usize] == corner
+        && state.co[corner as usize] == 0
+        && state.ep[edge as usize] == edge
+        && state.eo[edge as usize] == 0
+}
+
+fn cross_index(state: &CubieCube) -> usize {
+    cross_to_index(&state.ep, &state.eo) as usize
+}
+
+/// Number of moves to the solved cross of every cross coordinate, see `cross_to_index`.
+/// Built once by a breadth-first search from the solved cross. `index_to_ep_cross` and `index_to_eo_cross` only
+/// place the other edges around a solved cross, so the cross edges get their own coordinate.
+fn get_cross_distances() -> &'static [u8] {
+    static DISTANCES: OnceLock<Vec<u8>> = OnceLock::new();
+
+    DISTANCES.get_or_init(|| {
+        // `edge_moves[move][state]`, the state (`position * 2 + orientation`) of an edge after the move
+        let edge_moves = CROSS_MOVES.map(|m| {
+            let move_state = SOLVED_CUBIE_CUBE.apply_move(m);
+            let mut edges = [0; 24];
+
+            for i in 0..12 {
+                let pos = move_state.ep[i] as usize;
+                for o in 0..2 {
+                    edges[pos * 2 + o] = (i * 2 + (o + move_state.eo[i] as usize) % 2) as u8;
+                }
+            }
+
+            edges
+        });
+
+        let mut distances = vec![u8::MAX; CROSS_COUNT as usize];
+        let mut queue = VecDeque::from([0]);
+        distances[0] = 0;
+
+        while let Some(index) = queue.pop_front() {
+            let edges = index_to_cross_edges(index as u32);
+
+            for moved in &edge_moves {
+                let next = cross_edges_to_index(edges.map(|e| moved[e as usize])) as usize;
+
+                if distances[next] == u8::MAX {
+                    distances[next] = distances[index] + 1;
+                    queue.push_back(next);
+                }
+            }
+        }
+
+        distances
+    })
+}
+
+/// Returns the optimal cross. The distance table is exact, so the search only follows moves getting one move closer.
+pub fn solve_cross(state: &CubieCube) -> Vec<Move> {
+    let distances = get_cross_distances();
+    let mut state = *state;
+    let mut moves = vec![];
+
+    while distances[cross_index(&state)] > 0 {
+        let distance = distances[cross_index(&state)];
+        let next = CROSS_MOVES
+            .iter()
+            .map(|&m| (m, state.apply_move(m)))
+            .find(|(_, next)| distances[cross_index(next)] < distance)
+            .unwrap();
+
+        moves.push(next.0);
+        state = next.1;
+    }
+
+    moves
+}
+
+/// Distance table of the cross edges without D moves, shared by the F2L searches.
+fn get_f2l_cross_distances() -> Vec<u8> {
+    let pieces = CROSS_EDGES.map(Piece::Edge).to_vec();
+    let search = PieceSearch::new(pieces, &F2L_MOVES, &[vec![0, 1, 2, 3]]);
+
+    search.get_distances(0).to_vec()
+}
+
+/// Returns the slot index and the moves of the shortest insertion among the unsolved pairs,
+/// keeping the cross and the solved pairs.
+fn solve_next_pair(state: &CubieCube, cross_distances: &[u8]) -> Option<(usize, Vec<Move>)> {
+    let solved: Vec<usize> = (0..4)
+        .filter(|&i| is_pair_solved(state, F2L_PAIRS[i]))
+        .collect();
+    let mut searches = vec![];
+
+    for slot in (0..4).filter(|i| !solved.contains(i)) {
+        let mut pieces = CROSS_EDGES.map(Piece::Edge).to_vec();
+        let mut groups = vec![];
+
+        for &i in solved.iter().chain([slot].iter()) {
+            let (corner, edge) = F2L_PAIRS[i];
+            groups.push(vec![pieces.len(), pieces.len() + 1]);
+            pieces.push(Piece::Corner(corner));
+            pieces.push(Piece::Edge(edge));
+        }
+
+        let search = PieceSearch::new(pieces, &F2L_MOVES, &groups)
+            .with_distances(vec![0, 1, 2, 3], cross_distances.to_vec());
+        searches.push((slot, search));
+    }
+
+    (0..=PAIR_MAX_LENGTH).find_map(|depth| {
+        searches.iter().find_map(|(slot, search)| {
+            search
+                .solve_with_depth(state, depth)
+                .map(|moves| (*slot, moves))
+        })
+    })
+}
+
+/// Solves the cube with CFOP: optimal cross, first two layers pair by pair, OLL and PLL.
+/// The cross is solved on the D layer, each F2L stage is the shortest insertion among the remaining pairs.
+pub fn solve(state: CubieCube) -> Result<StagedSolution, Error> {
+    if !state.is_solvable() {
+        return Err(Error::InvalidCubieValue);
+    }
+
+    let mut stages = vec![];
+
+    let moves = solve_cross(&state);
+    let mut state = state.apply_moves(&moves);
+    stages.push(Stage { name: "Cross", moves });
+
+    let cross_distances = get_f2l_cross_distances();
+
+    for name in ["F2L 1", "F2L 2", "F2L 3", "F2L 4"] {
+        let moves = match solve_next_pair(&state, &cross_distances) {
+            Some((_, moves)) => moves,
+            None if F2L_PAIRS.iter().all(|&p| is_pair_solved(&state, p)) => vec![],
+            None => return Err(Error::InvalidCubieValue),
+        };
+
+        state = state.apply_moves(&moves);
+        stages.push(Stage { name, moves });
+    }
+
+    let moves = recognize_oll(&state)
+        .ok_or(Error::InvalidCubieValue)?
+        .get_all_moves();
+    state = state.apply_moves(&moves);
+    stages.push(Stage { name: "OLL", moves });
+
+    let moves = recognize_pll(&state)
+        .ok_or(Error::InvalidCubieValue)?
+        .get_all_moves();
+    stages.push(Stage { name: "PLL", moves });
+
+    Ok(StagedSolution { stages })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::cube::{
+        cubie::SOLVED_CUBIE_CUBE,
+        generators::{generate_random_state, generate_state_cross_solved},
+    };
+
+    #[test]
+    fn test_cross() {
+        // F' R' U' F', the cross needs 2 moves
+        let state = SOLVED_CUBIE_CUBE.apply_moves(&[F3, R3, U3, F3]);
+        let moves = solve_cross(&state);
+
+        assert_eq!(moves.len(), 2);
+        assert!(CROSS_MOVES.iter().all(|&m| !is_cross_solved(&state.apply_move(m))));
+        assert!(is_cross_solved(&state.apply_moves(&moves)));
+
+        let distances = get_cross_distances();
+        assert_eq!(distances.iter().max(), Some(&8));
+        assert!(solve_cross(&SOLVED_CUBIE_CUBE).is_empty());
+    }
+
+    #[test]
+    fn test_f2l_pair() {
+        // R U R', a single pair taken out
+        let state = SOLVED_CUBIE_CUBE.apply_moves(&[R, U, R3]);
+        let (slot, moves) = solve_next_pair(&state, &get_f2l_cross_distances()).unwrap();
+
+        assert_eq!(slot, 0);
+        assert_eq!(moves, vec![R, U3, R3]);
+    }
+
+    #[test]
+    fn test_solve() {
+        for state in [generate_random_state(), generate_state_cross_solved()] {
+            let solution = solve(state).unwrap();
+
+            assert_eq!(solution.stages.len(), 7);
+            assert!(is_cross_solved(&state.apply_moves(&solution.stages[0].moves)));
+            assert_eq!(state.apply_moves(&solution.get_all_moves()), SOLVED_CUBIE_CUBE);
+        }
+    }
+}
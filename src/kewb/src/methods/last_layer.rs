@@ -0,0 +1,397 @@
+use std::sync::OnceLock;
+
+use crate::{
+    cube::{cubie::CubieCube, moves::Move, scramble::scramble_from_str},
+    error::Error,
+};
+
+/// A named last layer case.
+/// `notation` is the algorithm as it is usually written (it may use wide and slice moves or rotations),
+/// `algorithm` is the same algorithm translated to face turns so it can be applied to a `CubieCube`.
+#[derive(Debug, PartialEq)]
+pub struct Case {
+    pub name: &'static str,
+    pub notation: &'static str,
+    pub algorithm: &'static str,
+}
+
+impl Case {
+    pub fn get_moves(&self) -> Result<Vec<Move>, Error> {
+        scramble_from_str(self.algorithm)
+    }
+}
+
+/// Case used when the last layer is already oriented.
+pub const OLL_SKIP: Case = Case {
+    name: "OLL skip",
+    notation: "",
+    algorithm: "",
+};
+
+/// Case used when the last layer is already permuted, up to a U layer adjustment.
+pub const PLL_SKIP: Case = Case {
+    name: "PLL skip",
+    notation: "",
+    algorithm: "",
+};
+
+/// The 57 OLL cases, numbered as in the usual OLL charts.
+#[rustfmt::skip]
+pub const OLL_CASES: [Case; 57] = [
+    Case { name: "OLL 1", notation: "R U2 R2 F R F' U2 R' F R F'", algorithm: "R U2 R2 F R F' U2 R' F R F'" },
+    Case { name: "OLL 2", notation: "F R U R' U' F' f R U R' U' f'", algorithm: "F R U R' U' F' B U L U' L' B'" },
+    Case { name: "OLL 3", notation: "f R U R' U' f' U' F R U R' U' F'", algorithm: "B U L U' L' B' U' F R U R' U' F'" },
+    Case { name: "OLL 4", notation: "f R U R' U' f' U F R U R' U' F'", algorithm: "B U L U' L' B' U F R U R' U' F'" },
+    Case { name: "OLL 5", notation: "r' U2 R U R' U r", algorithm: "L' B2 R B R' B L" },
+    Case { name: "OLL 6", notation: "r U2 R' U' R U' r'", algorithm: "L F2 R' F' R F' L'" },
+    Case { name: "OLL 7", notation: "r U R' U R U2 r'", algorithm: "L F R' F R F2 L'" },
+    Case { name: "OLL 8", notation: "r' U' R U' R' U2 r", algorithm: "L' B' R B' R' B2 L" },
+    Case { name: "OLL 9", notation: "R U R' U' R' F R2 U R' U' F'", algorithm: "R U R' U' R' F R2 U R' U' F'" },
+    Case { name: "OLL 10", notation: "R U R' U R' F R F' R U2 R'", algorithm: "R U R' U R' F R F' R U2 R'" },
+    Case { name: "OLL 11", notation: "r U R' U R' F R F' R U2 r'", algorithm: "L F R' F R' D R D' R F2 L'" },
+    Case { name: "OLL 12", notation: "M' R' U' R U' R' U2 R U' M", algorithm: "R' L R' F' R F' R' F2 R F' R L'" },
+    Case { name: "OLL 13", notation: "F U R U' R2 F' R U R U' R'", algorithm: "F U R U' R2 F' R U R U' R'" },
+    Case { name: "OLL 14", notation: "R' F R U R' F' R F U' F'", algorithm: "R' F R U R' F' R F U' F'" },
+    Case { name: "OLL 15", notation: "r' U' r R' U' R U r' U r", algorithm: "L' B' L R' U' R U L' B L" },
+    Case { name: "OLL 16", notation: "r U r' R U R' U' r U' r'", algorithm: "L F L' R U R' U' L F' L'" },
+    Case { name: "OLL 17", notation: "R U R' U R' F R F' U2 R' F R F'", algorithm: "R U R' U R' F R F' U2 R' F R F'" },
+    Case { name: "OLL 18", notation: "r U R' U R U2 r2 U' R U' R' U2 r", algorithm: "L F R' F R F2 L2 B' R B' R' B2 L" },
+    Case { name: "OLL 19", notation: "M U R U R' U' M' R' F R F'", algorithm: "R L' B R B R' B' R' L R' F R F'" },
+    Case { name: "OLL 20", notation: "r U R' U' M2 U R U' R' U' M'", algorithm: "L F R' F' R2 L2 B R B' R' B' R' L" },
+    Case { name: "OLL 21", notation: "R U2 R' U' R U R' U' R U' R'", algorithm: "R U2 R' U' R U R' U' R U' R'" },
+    Case { name: "OLL 22", notation: "R U2 R2 U' R2 U' R2 U2 R", algorithm: "R U2 R2 U' R2 U' R2 U2 R" },
+    Case { name: "OLL 23", notation: "R2 D' R U2 R' D R U2 R", algorithm: "R2 D' R U2 R' D R U2 R" },
+    Case { name: "OLL 24", notation: "r U R' U' r' F R F'", algorithm: "L F R' F' L' F R F'" },
+    Case { name: "OLL 25", notation: "F' r U R' U' r' F R", algorithm: "F' L F R' F' L' F R" },
+    Case { name: "OLL 26", notation: "R U2 R' U' R U' R'", algorithm: "R U2 R' U' R U' R'" },
+    Case { name: "OLL 27", notation: "R U R' U R U2 R'", algorithm: "R U R' U R U2 R'" },
+    Case { name: "OLL 28", notation: "r U R' U' M U R U' R'", algorithm: "L F R' F' R L' U R U' R'" },
+    Case { name: "OLL 29", notation: "R U R' U' R U' R' F' U' F R U R'", algorithm: "R U R' U' R U' R' F' U' F R U R'" },
+    Case { name: "OLL 30", notation: "F R' F R2 U' R' U' R U R' F2", algorithm: "F R' F R2 U' R' U' R U R' F2" },
+    Case { name: "OLL 31", notation: "R' U' F U R U' R' F' R", algorithm: "R' U' F U R U' R' F' R" },
+    Case { name: "OLL 32", notation: "L U F' U' L' U L F L'", algorithm: "L U F' U' L' U L F L'" },
+    Case { name: "OLL 33", notation: "R U R' U' R' F R F'", algorithm: "R U R' U' R' F R F'" },
+    Case { name: "OLL 34", notation: "R U R2 U' R' F R U R U' F'", algorithm: "R U R2 U' R' F R U R U' F'" },
+    Case { name: "OLL 35", notation: "R U2 R2 F R F' R U2 R'", algorithm: "R U2 R2 F R F' R U2 R'" },
+    Case { name: "OLL 36", notation: "L' U' L U' L' U L U L F' L' F", algorithm: "L' U' L U' L' U L U L F' L' F" },
+    Case { name: "OLL 37", notation: "F R' F' R U R U' R'", algorithm: "F R' F' R U R U' R'" },
+    Case { name: "OLL 38", notation: "R U R' U R U' R' U' R' F R F'", algorithm: "R U R' U R U' R' U' R' F R F'" },
+    Case { name: "OLL 39", notation: "L F' L' U' L U F U' L'", algorithm: "L F' L' U' L U F U' L'" },
+    Case { name: "OLL 40", notation: "R' F R U R' U' F' U R", algorithm: "R' F R U R' U' F' U R" },
+    Case { name: "OLL 41", notation: "R U R' U R U2 R' F R U R' U' F'", algorithm: "R U R' U R U2 R' F R U R' U' F'" },
+    Case { name: "OLL 42", notation: "R' U' R U' R' U2 R F R U R' U' F'", algorithm: "R' U' R U' R' U2 R F R U R' U' F'" },
+    Case { name: "OLL 43", notation: "f' L' U' L U f", algorithm: "B' U' R' U R B" },
+    Case { name: "OLL 44", notation: "f R U R' U' f'", algorithm: "B U L U' L' B'" },
+    Case { name: "OLL 45", notation: "F R U R' U' F'", algorithm: "F R U R' U' F'" },
+    Case { name: "OLL 46", notation: "R' U' R' F R F' U R", algorithm: "R' U' R' F R F' U R" },
+    Case { name: "OLL 47", notation: "R' U' R' F R F' R' F R F' U R", algorithm: "R' U' R' F R F' R' F R F' U R" },
+    Case { name: "OLL 48", notation: "F R U R' U' R U R' U' F'", algorithm: "F R U R' U' R U R' U' F'" },
+    Case { name: "OLL 49", notation: "r U' r2 U r2 U r2 U' r", algorithm: "L F' L2 B L2 F L2 B' L" },
+    Case { name: "OLL 50", notation: "r' U r2 U' r2 U' r2 U r'", algorithm: "L' B L2 F' L2 B' L2 F L'" },
+    Case { name: "OLL 51", notation: "F U R U' R' U R U' R' F'", algorithm: "F U R U' R' U R U' R' F'" },
+    Case { name: "OLL 52", notation: "R U R' U R U' B U' B' R'", algorithm: "R U R' U R U' B U' B' R'" },
+    Case { name: "OLL 53", notation: "r' U' R U' R' U R U' R' U2 r", algorithm: "L' B' R B' R' B R B' R' B2 L" },
+    Case { name: "OLL 54", notation: "r U R' U R U' R' U R U2 r'", algorithm: "L F R' F R F' R' F R F2 L'" },
+    Case { name: "OLL 55", notation: "R' F R U R U' R2 F' R2 U' R' U R U R'", algorithm: "R' F R U R U' R2 F' R2 U' R' U R U R'" },
+    Case { name: "OLL 56", notation: "r' U' r U' R' U R U' R' U R r' U r", algorithm: "L' B' L U' R' U R U' R' U R L' B L" },
+    Case { name: "OLL 57", notation: "R U R' U' M' U R U' r'", algorithm: "R U R' U' R' L F R F' L'" },
+];
+
+/// The 21 PLL cases.
+#[rustfmt::skip]
+pub const PLL_CASES: [Case; 21] = [
+    Case { name: "Aa-perm", notation: "x R' U R' D2 R U' R' D2 R2 x'", algorithm: "R' F R' B2 R F' R' B2 R2" },
+    Case { name: "Ab-perm", notation: "x R2 D2 R U R' D2 R U' R x'", algorithm: "R2 B2 R F R' B2 R F' R" },
+    Case { name: "E-perm", notation: "x' R U' R' D R U R' D' R U R' D R U' R' D' x", algorithm: "R B' R' F R B R' F' R B R' F R B' R' F'" },
+    Case { name: "F-perm", notation: "R' U' F' R U R' U' R' F R2 U' R' U' R U R' U R", algorithm: "R' U' F' R U R' U' R' F R2 U' R' U' R U R' U R" },
+    Case { name: "Ga-perm", notation: "R2 U R' U R' U' R U' R2 U' D R' U R D'", algorithm: "R2 U R' U R' U' R U' R2 U' D R' U R D'" },
+    Case { name: "Gb-perm", notation: "R' U' R U D' R2 U R' U R U' R U' R2 D", algorithm: "R' U' R U D' R2 U R' U R U' R U' R2 D" },
+    Case { name: "Gc-perm", notation: "R2 U' R U' R U R' U R2 U D' R U' R' D", algorithm: "R2 U' R U' R U R' U R2 U D' R U' R' D" },
+    Case { name: "Gd-perm", notation: "R U R' U' D R2 U' R U' R' U R' U R2 D'", algorithm: "R U R' U' D R2 U' R U' R' U R' U R2 D'" },
+    Case { name: "H-perm", notation: "R2 U2 R U2 R2 U2 R2 U2 R U2 R2", algorithm: "R2 U2 R U2 R2 U2 R2 U2 R U2 R2" },
+    Case { name: "Ja-perm", notation: "R' U L' U2 R U' R' U2 R L", algorithm: "R' U L' U2 R U' R' U2 R L" },
+    Case { name: "Jb-perm", notation: "R U R' F' R U R' U' R' F R2 U' R'", algorithm: "R U R' F' R U R' U' R' F R2 U' R'" },
+    Case { name: "Na-perm", notation: "R U R' U R U R' F' R U R' U' R' F R2 U' R' U2 R U' R'", algorithm: "R U R' U R U R' F' R U R' U' R' F R2 U' R' U2 R U' R'" },
+    Case { name: "Nb-perm", notation: "R' U R U' R' F' U' F R U R' F R' F' R U' R", algorithm: "R' U R U' R' F' U' F R U R' F R' F' R U' R" },
+    Case { name: "Ra-perm", notation: "R U' R' U' R U R D R' U' R D' R' U2 R'", algorithm: "R U' R' U' R U R D R' U' R D' R' U2 R'" },
+    Case { name: "Rb-perm", notation: "R2 F R U R U' R' F' R U2 R' U2 R", algorithm: "R2 F R U R U' R' F' R U2 R' U2 R" },
+    Case { name: "T-perm", notation: "R U R' U' R' F R2 U' R' U' R U R' F'", algorithm: "R U R' U' R' F R2 U' R' U' R U R' F'" },
+    Case { name: "Ua-perm", notation: "R U' R U R U R U' R' U' R2", algorithm: "R U' R U R U R U' R' U' R2" },
+    Case { name: "Ub-perm", notation: "R2 U R U R' U' R' U' R' U R'", algorithm: "R2 U R U R' U' R' U' R' U R'" },
+    Case { name: "V-perm", notation: "R' U R' U' y R' F' R2 U' R' U R' F R F", algorithm: "R' U R' U' B' R' B2 U' B' U B' R B R" },
+    Case { name: "Y-perm", notation: "F R U' R' U' R U R' F' R U R' U' R' F R F'", algorithm: "F R U' R' U' R U R' F' R U R' U' R' F R F'" },
+    Case { name: "Z-perm", notation: "M' U M2 U M2 U M' U2 M2", algorithm: "R' L F R2 L2 B R2 L2 F R' L D2 R2 L2" },
+];
+
+/// A recognized case, together with the U layer adjustments (AUF) needed around its algorithm.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CaseMatch {
+    pub case: &'static Case,
+    pub pre_auf: Option<Move>,
+    pub algorithm: Vec<Move>,
+    pub post_auf: Option<Move>,
+}
+
+impl CaseMatch {
+    pub fn get_all_moves(&self) -> Vec<Move> {
+        let mut moves: Vec<Move> = self.pre_auf.into_iter().collect();
+        moves.extend(&self.algorithm);
+        moves.extend(self.post_auf);
+        moves
+    }
+}
+
+/// Moves of the algorithm of each case, parsed on first use.
+fn case_moves(cases: &[Case], parsed: &'static OnceLock<Vec<Vec<Move>>>) -> &'static [Vec<Move>] {
+    parsed.get_or_init(|| cases.iter().map(|case| case.get_moves().unwrap()).collect())
+}
+
+pub(crate) fn oll_moves() -> &'static [Vec<Move>] {
+    static PARSED: OnceLock<Vec<Vec<Move>>> = OnceLock::new();
+    case_moves(&OLL_CASES, &PARSED)
+}
+
+pub(crate) fn pll_moves() -> &'static [Vec<Move>] {
+    static PARSED: OnceLock<Vec<Vec<Move>>> = OnceLock::new();
+    case_moves(&PLL_CASES, &PARSED)
+}
+
+const AUFS: [Option<Move>; 4] = [None, Some(Move::U), Some(Move::U2), Some(Move::U3)];
+
+fn apply_auf(state: CubieCube, auf: Option<Move>) -> CubieCube {
+    match auf {
+        Some(m) => state.apply_move(m),
+        None => state,
+    }
+}
+
+/// Checks if the first two layers (D layer and E slice) are solved.
+pub fn is_f2l_solved(state: &CubieCube) -> bool {
+    let corners = (4..8).all(|i| state.cp[i] as usize == i && state.co[i] == 0);
+    let edges = (0..4)
+        .chain(8..12)
+        .all(|i| state.ep[i] as usize == i && state.eo[i] == 0);
+
+    corners && edges
+}
+
+/// Checks if every U layer piece is oriented.
+pub fn is_ll_oriented(state: &CubieCube) -> bool {
+    state.co[..4].iter().all(|&co| co == 0) && state.eo[4..8].iter().all(|&eo| eo == 0)
+}
+
+/// Recognizes the OLL case of a state with the first two layers solved.
+/// Cases are matched up to AUF and y rotations: with the first two layers solved,
+/// a y rotation before the algorithm acts on the last layer like a U turn.
+/// Returns `OLL_SKIP` if the last layer is already oriented, and `None` if the first two layers are not solved.
+pub fn recognize_oll(state: &CubieCube) -> Option<CaseMatch> {
+    if !is_f2l_solved(state) {
+        return None;
+    }
+
+    if is_ll_oriented(state) {
+        return Some(CaseMatch {
+            case: &OLL_SKIP,
+            pre_auf: None,
+            algorithm: vec![],
+            post_auf: None,
+        });
+    }
+
+    for pre_auf in AUFS {
+        let adjusted = apply_auf(*state, pre_auf);
+
+        for (case, algorithm) in OLL_CASES.iter().zip(oll_moves()) {
+            if is_ll_oriented(&adjusted.apply_moves(algorithm)) {
+                return Some(CaseMatch {
+                    case,
+                    pre_auf,
+                    algorithm: algorithm.clone(),
+                    post_auf: None,
+                });
+            }
+        }
+    }
+
+    None
+}
+
+/// Recognizes the PLL case of a state with the first two layers solved and the last layer oriented.
+/// Cases are matched up to AUF and y rotations, see `recognize_oll`.
+/// Returns `PLL_SKIP` (with the final AUF) if only a U turn is left, and `None` if the state is not at the PLL stage.
+pub fn recognize_pll(state: &CubieCube) -> Option<CaseMatch> {
+    if !is_f2l_solved(state) || !is_ll_oriented(state) {
+        return None;
+    }
+
+    let solved = CubieCube::default();
+
+    for post_auf in AUFS {
+        if apply_auf(*state, post_auf) == solved {
+            return Some(CaseMatch {
+                case: &PLL_SKIP,
+                pre_auf: None,
+                algorithm: vec![],
+                post_auf,
+            });
+        }
+    }
+
+    for pre_auf in AUFS {
+        let adjusted = apply_auf(*state, pre_auf);
+
+        for (case, algorithm) in PLL_CASES.iter().zip(pll_moves()) {
+            let permuted = adjusted.apply_moves(algorithm);
+
+            for post_auf in AUFS {
+                if apply_auf(permuted, post_auf) == solved {
+                    return Some(CaseMatch {
+                        case,
+                        pre_auf,
+                        algorithm: algorithm.clone(),
+                        post_auf,
+                    });
+                }
+            }
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::cube::{
+        cubie::SOLVED_CUBIE_CUBE,
+        generators::{generate_state_f2l_solved, generate_state_oll_solved},
+    };
+
+    /// Face turns of a notation with wide moves, slice moves and rotations, following the faces moved by the rotations.
+    fn face_turns(notation: &str) -> Vec<Move> {
+        // Face of the solved cube in each position U, R, F, D, L, B.
+        let mut faces = *b"URFDLB";
+        let position = |face: u8| b"URFDLB".iter().position(|&f| f == face).unwrap();
+        let mut moves = vec![];
+
+        for token in notation.split_whitespace() {
+            let (letter, suffix) = token.split_at(1);
+            let turns = match suffix {
+                "" => 1,
+                "2" => 2,
+                "'" => 3,
+                _ => panic!("invalid turn {token}"),
+            };
+            let inverse = 4 - turns;
+            let (layers, rotation) = match letter {
+                "r" => (vec![(b'L', turns)], Some((b'x', turns))),
+                "l" => (vec![(b'R', turns)], Some((b'x', inverse))),
+                "u" => (vec![(b'D', turns)], Some((b'y', turns))),
+                "d" => (vec![(b'U', turns)], Some((b'y', inverse))),
+                "f" => (vec![(b'B', turns)], Some((b'z', turns))),
+                "b" => (vec![(b'F', turns)], Some((b'z', inverse))),
+                "M" => (vec![(b'R', turns), (b'L', inverse)], Some((b'x', inverse))),
+                "x" | "y" | "z" => (vec![], Some((letter.as_bytes()[0], turns))),
+                _ => (vec![(letter.as_bytes()[0], turns)], None),
+            };
+
+            for (layer, turns) in layers {
+                let face = faces[position(layer)] as char;
+                let suffix = ["", "", "2", "'"][turns];
+                moves.push(format!("{face}{suffix}").parse().unwrap());
+            }
+
+            if let Some((axis, turns)) = rotation {
+                // Positions whose face moves to the previous position of the cycle.
+                let cycle = match axis {
+                    b'x' => b"UFDB",
+                    b'y' => b"FRBL",
+                    _ => b"ULDR",
+                };
+
+                for _ in 0..turns {
+                    let moved = faces;
+                    for i in 0..4 {
+                        faces[position(cycle[i])] = moved[position(cycle[(i + 1) % 4])];
+                    }
+                }
+            }
+        }
+
+        moves
+    }
+
+    fn case_state(case: &Case, auf: Option<Move>) -> CubieCube {
+        let inverse: Vec<Move> = case
+            .get_moves()
+            .unwrap()
+            .iter()
+            .rev()
+            .map(|m| m.get_inverse())
+            .collect();
+
+        apply_auf(SOLVED_CUBIE_CUBE.apply_moves(&inverse), auf)
+    }
+
+    #[test]
+    fn test_notation() {
+        for case in OLL_CASES.iter().chain(PLL_CASES.iter()) {
+            assert_eq!(face_turns(case.notation), case.get_moves().unwrap(), "{}", case.name);
+        }
+    }
+
+    #[test]
+    fn test_recognize_oll() {
+        for case in OLL_CASES.iter() {
+            for auf in AUFS {
+                let state = case_state(case, auf);
+                let found = recognize_oll(&state).unwrap();
+
+                assert_eq!(found.case.name, case.name);
+                assert!(is_ll_oriented(&state.apply_moves(&found.get_all_moves())));
+            }
+        }
+    }
+
+    #[test]
+    fn test_recognize_pll() {
+        for case in PLL_CASES.iter() {
+            for auf in AUFS {
+                let state = case_state(case, auf);
+                let found = recognize_pll(&state).unwrap();
+
+                assert_eq!(found.case.name, case.name);
+                assert_eq!(state.apply_moves(&found.get_all_moves()), SOLVED_CUBIE_CUBE);
+            }
+        }
+    }
+
+    #[test]
+    fn test_skip() {
+        assert_eq!(recognize_oll(&SOLVED_CUBIE_CUBE).unwrap().case, &OLL_SKIP);
+
+        let state = SOLVED_CUBIE_CUBE.apply_move(Move::U);
+        let found = recognize_pll(&state).unwrap();
+
+        assert_eq!(found.case, &PLL_SKIP);
+        assert_eq!(found.post_auf, Some(Move::U3));
+        assert!(recognize_pll(&SOLVED_CUBIE_CUBE.apply_move(Move::R)).is_none());
+    }
+
+    #[test]
+    fn test_generated_states() {
+        for _ in 0..20 {
+            let state = generate_state_f2l_solved();
+            let oll = recognize_oll(&state).unwrap();
+            let state = state.apply_moves(&oll.get_all_moves());
+            let pll = recognize_pll(&state).unwrap();
+
+            assert_eq!(state.apply_moves(&pll.get_all_moves()), SOLVED_CUBIE_CUBE);
+
+            let state = generate_state_oll_solved();
+            let pll = recognize_pll(&state).unwrap();
+
+            assert_eq!(state.apply_moves(&pll.get_all_moves()), SOLVED_CUBIE_CUBE);
+        }
+    }
+}
@@ -0,0 +1,64 @@
+use std::fmt;
+
+use crate::cube::moves::Move;
+
+pub mod beginner;
+pub mod cfop;
+pub mod last_layer;
+pub mod roux;
+pub mod search;
+pub mod utils;
+
+/// A named step of a human method.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Stage {
+    pub name: &'static str,
+    pub moves: Vec<Move>,
+}
+
+impl fmt::Display for Stage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let stringified = self
+            .moves
+            .iter()
+            .map(|m| m.to_string())
+            .collect::<Vec<_>>()
+            .join(" ");
+
+        write!(f, "{stringified}")
+    }
+}
+
+/// Solution split into the named stages of a human method.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct StagedSolution {
+    pub stages: Vec<Stage>,
+}
+
+impl StagedSolution {
+    pub fn len(&self) -> usize {
+        self.stages.iter().map(|s| s.moves.len()).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.stages.iter().all(|s| s.moves.is_empty())
+    }
+
+    pub fn get_all_moves(&self) -> Vec<Move> {
+        self.stages.iter().flat_map(|s| s.moves.clone()).collect()
+    }
+}
+
+impl fmt::Display for StagedSolution {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let stringified = self
+            .stages
+            .iter()
+            .filter(|s| !s.moves.is_empty())
+            .map(|s| s.to_string())
+            .collect::<Vec<_>>()
+            .join(" ");
+
+        write!(f, "{stringified}")
+    }
+}
@@ -0,0 +1,246 @@
+use std::collections::{HashMap, VecDeque};
+
+use super::{
+    last_layer::{oll_moves, pll_moves},
+    search::{Piece, PieceSearch},
+    utils::{push_moves, split_move},
+    Stage, StagedSolution,
+};
+use crate::{
+    cube::{
+        cubie::{Corner, CubieCube, Edge, SOLVED_CUBIE_CUBE},
+        moves::Move::{self, *},
+    },
+    error::Error,
+};
+
+/// The first block is the left 1x2x3 block, the second block the right one.
+/// Each block is built as a back square, then the front pair.
+const FIRST_SQUARE: [Piece; 3] = [
+    Piece::Edge(Edge::DL),
+    Piece::Edge(Edge::BL),
+    Piece::Corner(Corner::DBL),
+];
+const FIRST_PAIR: [Piece; 2] = [Piece::Edge(Edge::FL), Piece::Corner(Corner::DFL)];
+const SECOND_SQUARE: [Piece; 3] = [
+    Piece::Edge(Edge::DR),
+    Piece::Edge(Edge::BR),
+    Piece::Corner(Corner::DBR),
+];
+const SECOND_PAIR: [Piece; 2] = [Piece::Edge(Edge::FR), Piece::Corner(Corner::DFR)];
+
+#[rustfmt::skip]
+const FIRST_BLOCK_MOVES: [Move; 21] = [
+    U, U2, U3, D, D2, D3, R, R2, R3, L, L2, L3, F, F2, F3, B, B2, B3, M, M2, M3,
+];
+/// R, U and M moves never touch the first block, so the second block is built with them only.
+const SECOND_BLOCK_MOVES: [Move; 9] = [U, U2, U3, R, R2, R3, M, M2, M3];
+const LSE_MOVES: [Move; 6] = [U, U2, U3, M, M2, M3];
+
+const SQUARE_MAX_LENGTH: u8 = 10;
+const PAIR_MAX_LENGTH: u8 = 14;
+
+/// Name of the stage solving the last layer corners.
+pub const LL_CORNERS_STAGE: &str = "Corners (OLL + PLL)";
+
+const AUFS: [Option<Move>; 4] = [None, Some(U), Some(U2), Some(U3)];
+
+/// The six edges left after the last layer corners: the M slice and the UL and UR edges.
+const LSE_EDGES: [Edge; 6] = [Edge::UB, Edge::UR, Edge::UF, Edge::UL, Edge::DF, Edge::DB];
+
+/// Solves a block: the square first, then the pair while keeping the square.
+fn solve_block(
+    state: &CubieCube,
+    square: &[Piece; 3],
+    pair: &[Piece; 2],
+    moves: &[Move],
+) -> Option<Vec<Move>> {
+    let search = PieceSearch::new(square.to_vec(), moves, &[vec![0, 1, 2]]);
+    let mut solution = search.solve(state, SQUARE_MAX_LENGTH)?;
+    let state = state.apply_moves(&solution);
+
+    // the pair group shares the bottom edge of the block with the square
+    let pieces = square.iter().chain(pair.iter()).copied().collect();
+    let search = PieceSearch::new(pieces, moves, &[vec![0, 1, 2], vec![0, 3, 4]]);
+    push_moves(&mut solution, &search.solve(&state, PAIR_MAX_LENGTH)?);
+
+    Some(solution)
+}
+
+fn are_corners_oriented(state: &CubieCube) -> bool {
+    state.co[..4].iter().all(|&co| co == 0)
+}
+
+fn are_corners_solved(state: &CubieCube) -> bool {
+    are_corners_oriented(state) && (0..4).all(|i| state.cp[i] as usize == i)
+}
+
+/// Returns the shortest adjustment and algorithm among `cases`, or no algorithm, followed by the U layer
+/// adjustments in `post_aufs`, reaching a state accepted by `is_done`.
+fn find_algorithm(
+    state: &CubieCube,
+    cases: &[Vec<Move>],
+    post_aufs: &[Option<Move>],
+    is_done: fn(&CubieCube) -> bool,
+) -> Option<Vec<Move>> {
+    let mut best: Option<Vec<Move>> = None;
+
+    for pre_auf in AUFS {
+        for case in [&[][..]].into_iter().chain(cases.iter().map(Vec::as_slice)) {
+            for post_auf in post_aufs {
+                let mut moves = vec![];
+                moves.extend(pre_auf);
+                push_moves(&mut moves, case);
+                moves.extend(*post_auf);
+
+                let shorter = best.as_ref().is_none_or(|b| moves.len() < b.len());
+                if shorter && is_done(&state.apply_moves(&moves)) {
+                    best = Some(moves);
+                }
+            }
+        }
+    }
+
+    best
+}
+
+/// Solves the U layer corners in two looks, orienting them with an OLL algorithm and permuting them
+/// with a PLL algorithm. Both keep the blocks, the M slice edges are ignored.
+/// This stands in for CMLL, which solves them in one look with dedicated algorithms.
+fn solve_ll_corners(state: &CubieCube) -> Option<Vec<Move>> {
+    let mut moves = find_algorithm(state, oll_moves(), &[None], are_corners_oriented)?;
+    let oriented = state.apply_moves(&moves);
+    push_moves(
+        &mut moves,
+        &find_algorithm(&oriented, pll_moves(), &AUFS, are_corners_solved)?,
+    );
+
+    Some(moves)
+}
+
+/// Returns the number of clockwise quarter turns the M slice centers made during the moves.
+fn count_center_turns(moves: &[Move]) -> u8 {
+    moves
+        .iter()
+        .map(|&m| split_move(m))
+        .filter(|&(layer, _)| layer == split_move(M).0)
+        .fold(0, |acc, (_, turns)| (acc + turns) % 4)
+}
+
+/// Key of a state after the last layer corners: the six remaining edges, the U layer adjustment and the centers.
+fn lse_key(state: &CubieCube, centers: u8) -> u64 {
+    let edges = LSE_EDGES.iter().fold(0, |acc, &e| {
+        let pos = e as usize;
+        acc * 32 + state.ep[pos] as u64 * 2 + state.eo[pos] as u64
+    });
+
+    (edges * 4 + state.cp[0] as u64) * 4 + centers as u64
+}
+
+/// Solves the last six edges and the centers with a breadth-first search over U and M moves.
+/// `centers` is the number of clockwise quarter turns of the M slice centers.
+fn solve_lse(state: &CubieCube, centers: u8) -> Option<Vec<Move>> {
+    let start = lse_key(state, centers);
+    let mut parents: HashMap<u64, (u64, Move)> = HashMap::new();
+    let mut queue = VecDeque::from([(*state, centers)]);
+    let mut found = None;
+
+    while let Some((current, centers)) = queue.pop_front() {
+        let key = lse_key(&current, centers);
+
+        if current == SOLVED_CUBIE_CUBE && centers == 0 {
+            found = Some(key);
+            break;
+        }
+
+        for m in LSE_MOVES {
+            let next = current.apply_move(m);
+            let next_centers = (centers + count_center_turns(&[m])) % 4;
+            let next_key = lse_key(&next, next_centers);
+
+            if next_key != start && !parents.contains_key(&next_key) {
+                parents.insert(next_key, (key, m));
+                queue.push_back((next, next_centers));
+            }
+        }
+    }
+
+    let mut key = found?;
+    let mut moves = vec![];
+
+    while let Some(&(parent, m)) = parents.get(&key) {
+        moves.push(m);
+        key = parent;
+    }
+
+    moves.reverse();
+    Some(moves)
+}
+
+/// Solves the cube with Roux: first block, second block, last layer corners and the last six edges.
+/// The blocks are built on the L and R faces, the second block and LSE use M slice moves.
+pub fn solve(state: CubieCube) -> Result<StagedSolution, Error> {
+    if !state.is_solvable() {
+        return Err(Error::InvalidCubieValue);
+    }
+
+    let moves = solve_block(&state, &FIRST_SQUARE, &FIRST_PAIR, &FIRST_BLOCK_MOVES)
+        .ok_or(Error::InvalidCubieValue)?;
+    let mut state = state.apply_moves(&moves);
+    let mut centers = count_center_turns(&moves);
+    let mut stages = vec![Stage {
+        name: "First block",
+        moves,
+    }];
+
+    let moves = solve_block(&state, &SECOND_SQUARE, &SECOND_PAIR, &SECOND_BLOCK_MOVES)
+        .ok_or(Error::InvalidCubieValue)?;
+    state = state.apply_moves(&moves);
+    centers = (centers + count_center_turns(&moves)) % 4;
+    stages.push(Stage {
+        name: "Second block",
+        moves,
+    });
+
+    let moves = solve_ll_corners(&state).ok_or(Error::InvalidCubieValue)?;
+    state = state.apply_moves(&moves);
+    stages.push(Stage {
+        name: LL_CORNERS_STAGE,
+        moves,
+    });
+
+    let moves = solve_lse(&state, centers).ok_or(Error::InvalidCubieValue)?;
+    stages.push(Stage { name: "LSE", moves });
+
+    Ok(StagedSolution { stages })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::cube::generators::generate_random_state;
+
+    #[test]
+    fn test_lse() {
+        // M' U M' U2 M U M'
+        let scramble = [M3, U, M3, U2, M, U, M3];
+        let state = SOLVED_CUBIE_CUBE.apply_moves(&scramble);
+        let centers = count_center_turns(&scramble);
+        let moves = solve_lse(&state, centers).unwrap();
+
+        assert!(moves.len() <= scramble.len());
+        assert_eq!(state.apply_moves(&moves), SOLVED_CUBIE_CUBE);
+        assert_eq!((centers + count_center_turns(&moves)) % 4, 0);
+    }
+
+    #[test]
+    fn test_solve() {
+        let state = generate_random_state();
+        let solution = solve(state).unwrap();
+        let moves = solution.get_all_moves();
+
+        assert_eq!(solution.stages.len(), 4);
+        assert_eq!(state.apply_moves(&moves), SOLVED_CUBIE_CUBE);
+        assert_eq!(count_center_turns(&moves), 0);
+    }
+}
@@ -0,0 +1,243 @@
+use std::collections::VecDeque;
+
+use crate::cube::{
+    cubie::{Corner, CubieCube, Edge},
+    moves::{is_move_available, Move},
+};
+
+/// A piece followed by a `PieceSearch`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Piece {
+    Edge(Edge),
+    Corner(Corner),
+}
+
+/// The state of a piece: `position * 2 + orientation` for edges, `position * 3 + orientation` for corners.
+/// Both kinds have 24 states.
+const PIECE_STATES: usize = 24;
+const MAX_PIECES: usize = 20;
+
+type PieceStates = [u8; MAX_PIECES];
+
+impl Piece {
+    fn home(&self) -> u8 {
+        match self {
+            Piece::Edge(e) => *e as u8 * 2,
+            Piece::Corner(c) => *c as u8 * 3,
+        }
+    }
+
+    fn state(&self, cube: &CubieCube) -> u8 {
+        match self {
+            Piece::Edge(e) => {
+                let pos = cube.ep.iter().position(|p| p == e).unwrap();
+                pos as u8 * 2 + cube.eo[pos]
+            }
+            Piece::Corner(c) => {
+                let pos = cube.cp.iter().position(|p| p == c).unwrap();
+                pos as u8 * 3 + cube.co[pos]
+            }
+        }
+    }
+}
+
+/// Distance table for a group of pieces, indexed by their combined states.
+struct Pattern {
+    pieces: Vec<usize>,
+    distances: Vec<u8>,
+}
+
+/// Iterative deepening search bringing a set of pieces home, other pieces are ignored.
+/// The heuristic is the maximum distance over groups of pieces, each group having its own
+/// breadth-first distance table, so groups should be kept to a few pieces.
+pub struct PieceSearch {
+    pieces: Vec<Piece>,
+    moves: Vec<Move>,
+    /// `edge_moves[move][state]`, the state of an edge after the move.
+    edge_moves: Vec<[u8; PIECE_STATES]>,
+    /// `corner_moves[move][state]`, the state of a corner after the move.
+    corner_moves: Vec<[u8; PIECE_STATES]>,
+    patterns: Vec<Pattern>,
+}
+
+impl PieceSearch {
+    /// Creates a search for `pieces` using `moves`, `groups` holds indices into `pieces`.
+    pub fn new(pieces: Vec<Piece>, moves: &[Move], groups: &[Vec<usize>]) -> Self {
+        let mut edge_moves = vec![];
+        let mut corner_moves = vec![];
+
+        for m in moves {
+            let move_state = CubieCube::default().apply_move(*m);
+            let mut edges = [0; PIECE_STATES];
+            let mut corners = [0; PIECE_STATES];
+
+            for i in 0..12 {
+                let pos = move_state.ep[i] as usize;
+                for o in 0..2 {
+                    edges[pos * 2 + o] = (i * 2 + (o + move_state.eo[i] as usize) % 2) as u8;
+                }
+            }
+
+            for i in 0..8 {
+                let pos = move_state.cp[i] as usize;
+                for o in 0..3 {
+                    corners[pos * 3 + o] = (i * 3 + (o + move_state.co[i] as usize) % 3) as u8;
+                }
+            }
+
+            edge_moves.push(edges);
+            corner_moves.push(corners);
+        }
+
+        let mut search = Self {
+            pieces,
+            moves: moves.to_vec(),
+            edge_moves,
+            corner_moves,
+            patterns: vec![],
+        };
+
+        for group in groups {
+            let distances = search.get_distance_table(group);
+            search.patterns.push(Pattern {
+                pieces: group.clone(),
+                distances,
+            });
+        }
+
+        search
+    }
+
+    /// Returns the distance table of the `index`-th group.
+    pub fn get_distances(&self, index: usize) -> &[u8] {
+        &self.patterns[index].distances
+    }
+
+    /// Adds a group with an already computed distance table, as returned by `get_distances`
+    /// for a search sharing the same pieces and moves.
+    pub fn with_distances(mut self, group: Vec<usize>, distances: Vec<u8>) -> Self {
+        self.patterns.push(Pattern {
+            pieces: group,
+            distances,
+        });
+        self
+    }
+
+    fn next(&self, states: &PieceStates, move_index: usize) -> PieceStates {
+        let mut next = *states;
+
+        for (i, piece) in self.pieces.iter().enumerate() {
+            next[i] = match piece {
+                Piece::Edge(_) => self.edge_moves[move_index][states[i] as usize],
+                Piece::Corner(_) => self.corner_moves[move_index][states[i] as usize],
+            };
+        }
+
+        next
+    }
+
+    fn pattern_index(group: &[usize], states: &PieceStates) -> usize {
+        group
+            .iter()
+            .fold(0, |acc, &i| acc * PIECE_STATES + states[i] as usize)
+    }
+
+    fn get_distance_table(&self, group: &[usize]) -> Vec<u8> {
+        let mut distances = vec![u8::MAX; PIECE_STATES.pow(group.len() as u32)];
+        let mut queue = VecDeque::new();
+        let mut solved = [0; MAX_PIECES];
+
+        for (i, piece) in self.pieces.iter().enumerate() {
+            solved[i] = piece.home();
+        }
+
+        distances[Self::pattern_index(group, &solved)] = 0;
+        queue.push_back(solved);
+
+        while let Some(states) = queue.pop_front() {
+            let distance = distances[Self::pattern_index(group, &states)];
+
+            for move_index in 0..self.moves.len() {
+                let next = self.next(&states, move_index);
+                let index = Self::pattern_index(group, &next);
+
+                if distances[index] == u8::MAX {
+                    distances[index] = distance + 1;
+                    queue.push_back(next);
+                }
+            }
+        }
+
+        distances
+    }
+
+    fn heuristic(&self, states: &PieceStates) -> u8 {
+        self.patterns
+            .iter()
+            .map(|p| p.distances[Self::pattern_index(&p.pieces, states)])
+            .max()
+            .unwrap_or(0)
+    }
+
+    fn is_solved(&self, states: &PieceStates) -> bool {
+        self.pieces
+            .iter()
+            .enumerate()
+            .all(|(i, piece)| states[i] == piece.home())
+    }
+
+    fn get_states(&self, state: &CubieCube) -> PieceStates {
+        let mut states = [0; MAX_PIECES];
+
+        for (i, piece) in self.pieces.iter().enumerate() {
+            states[i] = piece.state(state);
+        }
+
+        states
+    }
+
+    /// Returns the shortest sequence of at most `max_depth` moves bringing every piece home.
+    pub fn solve(&self, state: &CubieCube, max_depth: u8) -> Option<Vec<Move>> {
+        (0..=max_depth).find_map(|depth| self.solve_with_depth(state, depth))
+    }
+
+    /// Returns a sequence of exactly `depth` moves bringing every piece home.
+    pub fn solve_with_depth(&self, state: &CubieCube, depth: u8) -> Option<Vec<Move>> {
+        let states = self.get_states(state);
+        let mut path = vec![];
+
+        if self.search(&states, depth, &mut path) {
+            Some(path.iter().map(|&i| self.moves[i]).collect())
+        } else {
+            None
+        }
+    }
+
+    fn search(&self, states: &PieceStates, depth: u8, path: &mut Vec<usize>) -> bool {
+        if depth == 0 {
+            return self.is_solved(states);
+        }
+
+        if self.heuristic(states) > depth {
+            return false;
+        }
+
+        for move_index in 0..self.moves.len() {
+            if let Some(&prev) = path.last() {
+                if !is_move_available(self.moves[prev], self.moves[move_index]) {
+                    continue;
+                }
+            }
+
+            path.push(move_index);
+
+            if self.search(&self.next(states, move_index), depth - 1, path) {
+                return true;
+            }
+
+            path.pop();
+        }
+
+        false
+    }
+}
@@ -0,0 +1,68 @@
+use crate::cube::moves::Move::{self, *};
+
+#[rustfmt::skip]
+const FACE_MOVES: [[Move; 3]; 7] = [
+    [U, U2, U3],
+    [D, D2, D3],
+    [R, R2, R3],
+    [L, L2, L3],
+    [F, F2, F3],
+    [B, B2, B3],
+    [M, M2, M3],
+];
+
+/// Splits a move into its layer (index in `FACE_MOVES`) and its number of clockwise quarter turns.
+pub fn split_move(m: Move) -> (usize, u8) {
+    let index = m as usize;
+    (index / 3, (index % 3) as u8 + 1)
+}
+
+/// Builds a move from its layer and a number of clockwise quarter turns, `None` if the turns cancel out.
+pub fn join_move(layer: usize, turns: u8) -> Option<Move> {
+    match turns % 4 {
+        0 => None,
+        t => Some(FACE_MOVES[layer][t as usize - 1]),
+    }
+}
+
+/// Pushes a move, merging it with the previous one when they turn the same layer.
+pub fn push_move(moves: &mut Vec<Move>, m: Move) {
+    let (layer, turns) = split_move(m);
+
+    if let Some(&prev) = moves.last() {
+        let (prev_layer, prev_turns) = split_move(prev);
+
+        if prev_layer == layer {
+            moves.pop();
+            moves.extend(join_move(layer, prev_turns + turns));
+            return;
+        }
+    }
+
+    moves.push(m);
+}
+
+/// Pushes a sequence of moves, see `push_move`.
+pub fn push_moves(moves: &mut Vec<Move>, other: &[Move]) {
+    for &m in other {
+        push_move(moves, m);
+    }
+}
+
+/// Relabels the side faces of a move as if the cube was rotated by `times` y' rotations,
+/// so an algorithm written for the front-right slot works on the front-left, back-left and back-right slots.
+/// U, D and M moves are kept as is.
+pub fn rotate_move(m: Move, times: usize) -> Move {
+    const SIDES: [usize; 4] = [2, 4, 3, 5]; // R -> F -> L -> B
+    let (layer, turns) = split_move(m);
+
+    match SIDES.iter().position(|&s| s == layer) {
+        Some(i) => join_move(SIDES[(i + times) % 4], turns).unwrap(),
+        None => m,
+    }
+}
+
+/// Relabels every move of a sequence, see `rotate_move`.
+pub fn rotate_moves(moves: &[Move], times: usize) -> Vec<Move> {
+    moves.iter().map(|&m| rotate_move(m, times)).collect()
+}
@@ -1,5 +1,5 @@
-use super::utils::DataTable;
-use crate::error::Error;
+use super::utils::{DataTable, ALL_MOVES, PHASE2_MOVES};
+use crate::{cube::constants::*, error::Error};
 use bincode::{
     config::{self, Configuration},
     decode_from_slice, encode_to_vec,
@@ -7,20 +7,152 @@ use bincode::{
 };
 use std::{fs, path::Path};
 
+#[cfg(not(target_arch = "wasm32"))]
+use {
+    super::{
+        moves::MoveTable,
+        pruning::PruningTable,
+        utils::{Entry, Table},
+    },
+    memmap2::Mmap,
+    std::sync::Arc,
+};
+
 const CONFIG: Configuration = config::standard();
 
+/// First bytes of bincode encoded tables.
+const MAGIC: [u8; 4] = *b"KEWB";
+
+/// First bytes of a table file in the flat layout, see `encode_flat`.
+const FLAT_MAGIC: [u8; 4] = *b"KEWF";
+
+/// Version of the table file format, increased whenever the tables or their encoding change.
+/// Version 4 rejects tables generated before F' was restored to the phase 1 moves.
+pub const TABLE_VERSION: u32 = 4;
+
+/// The encoded tables follow the magic number, the format version, the move set hash and their checksum,
+/// numbers being little endian.
+const HEADER_SIZE: usize = 24;
+
+/// 64-bit FNV-1a hash, detecting corrupted table files.
+fn checksum(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325, |hash, &byte| {
+        (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+/// Hash of the moves the tables are indexed by and of the coordinate sizes, tables generated with another order
+/// of the moves or other coordinates decode fine but give wrong solutions.
+fn move_set_hash() -> u64 {
+    let moves = ALL_MOVES.iter().chain(&PHASE2_MOVES).map(|m| *m as u8);
+    let counts = [CO_COUNT, EO_COUNT, E_COMBO_COUNT, CP_COUNT, UD_EP_COUNT, E_EP_COUNT];
+    let mut bytes: Vec<u8> = moves.collect();
+
+    bytes.extend(counts.iter().flat_map(|count| count.to_le_bytes()));
+
+    checksum(&bytes)
+}
+
+fn with_header(magic: [u8; 4], payload: Vec<u8>) -> Vec<u8> {
+    let mut encoded = Vec::with_capacity(HEADER_SIZE + payload.len());
+
+    encoded.extend(magic);
+    encoded.extend(TABLE_VERSION.to_le_bytes());
+    encoded.extend(move_set_hash().to_le_bytes());
+    encoded.extend(checksum(&payload).to_le_bytes());
+    encoded.extend(payload);
+
+    encoded
+}
+
+/// Checks the header and gives the payload with its checksum, which is left to the caller.
+fn check_header(magic: [u8; 4], bytes: &[u8]) -> Result<(&[u8], u64), Error> {
+    if bytes.len() < HEADER_SIZE {
+        return Err(Error::CorruptedTable);
+    }
+
+    let (header, payload) = bytes.split_at(HEADER_SIZE);
+    let version = u32::from_le_bytes(header[4..8].try_into().unwrap());
+    let moves = u64::from_le_bytes(header[8..16].try_into().unwrap());
+    let sum = u64::from_le_bytes(header[16..].try_into().unwrap());
+
+    if header[..4] != magic {
+        let layout = |magic: &[u8]| match magic {
+            m if m == MAGIC => Some("bincode"),
+            m if m == FLAT_MAGIC => Some("flat"),
+            _ => None,
+        };
+        let found = match (layout(&header[..4]), layout(&magic)) {
+            (Some(found), Some(expected)) => format!("tables in the {found} layout, expected the {expected} layout"),
+            _ => "not a table file".to_string(),
+        };
+
+        return Err(Error::IncompatibleTable(found));
+    }
+
+    if version != TABLE_VERSION {
+        return Err(Error::IncompatibleTable(format!(
+            "version {version}, expected {TABLE_VERSION}"
+        )));
+    }
+
+    if moves != move_set_hash() {
+        return Err(Error::IncompatibleTable("generated with another move set".to_string()));
+    }
+
+    Ok((payload, sum))
+}
+
+/// Generates the tables and writes them to `path`.
 pub fn write_table<P>(path: P) -> Result<(), Error>
 where
     P: AsRef<Path>,
 {
-    let table = DataTable::default();
-    let encoded = encode_to_vec(table, CONFIG)?;
+    save_table(&DataTable::default(), path)
+}
+
+/// Writes the tables in the flat layout `read_table` maps.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn save_table<P>(table: &DataTable, path: P) -> Result<(), Error>
+where
+    P: AsRef<Path>,
+{
+    fs::write(path, encode_flat(table))?;
+
+    Ok(())
+}
 
-    fs::write(path, encoded)?;
+#[cfg(target_arch = "wasm32")]
+pub fn save_table<P>(table: &DataTable, path: P) -> Result<(), Error>
+where
+    P: AsRef<Path>,
+{
+    fs::write(path, encode_table(table)?)?;
 
     Ok(())
 }
 
+/// Maps the table file, the tables are read in place when the solver needs them.
+/// Only the header and the table sizes are checked, so that no page is read before it is needed. The checksum of
+/// the tables is checked by `verify_table`. The file must not be modified while the tables are used.
+/// Files of bincode encoded tables, see `encode_table`, are decoded into memory instead.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn read_table<P>(path: P) -> Result<DataTable, Error>
+where
+    P: AsRef<Path>,
+{
+    let file = fs::File::open(path)?;
+    // SAFETY: the map is only read, writing the file while it is mapped is documented as unsupported
+    let map = unsafe { Mmap::map(&file)? };
+
+    if map.starts_with(&MAGIC) {
+        return decode_table(&map);
+    }
+
+    decode_flat(&Arc::new(map))
+}
+
+#[cfg(target_arch = "wasm32")]
 pub fn read_table<P>(path: P) -> Result<DataTable, Error>
 where
     P: AsRef<Path>,
@@ -31,13 +163,279 @@ where
     Ok(table)
 }
 
+/// Reads the whole table file into memory, checking its checksum.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn verify_table<P>(path: P) -> Result<DataTable, Error>
+where
+    P: AsRef<Path>,
+{
+    let encoded = fs::read(path)?;
+
+    if encoded.starts_with(&MAGIC) {
+        return decode_table(&encoded);
+    }
+
+    let (payload, sum) = check_header(FLAT_MAGIC, &encoded)?;
+
+    if sum != checksum(payload) {
+        return Err(Error::CorruptedTable);
+    }
+
+    decode_flat(encoded.as_slice())
+}
+
+#[cfg(target_arch = "wasm32")]
+pub fn verify_table<P>(path: P) -> Result<DataTable, Error>
+where
+    P: AsRef<Path>,
+{
+    read_table(path)
+}
+
+/// Encodes the tables with bincode, with the header `decode_table` checks.
+/// Table files use the flat layout on native targets, this is meant for tables embedded in wasm builds.
+pub fn encode_table(table: &DataTable) -> Result<Vec<u8>, Error> {
+    let payload = encode_to_vec(table, CONFIG)?;
+
+    Ok(with_header(MAGIC, payload))
+}
+
 pub fn decode_table(bytes: &[u8]) -> Result<DataTable, Error> {
-    let (decoded, written) = decode_from_slice(bytes, CONFIG)?;
-    let additional = bytes.len() - written;
+    let (payload, sum) = check_header(MAGIC, bytes)?;
+
+    if sum != checksum(payload) {
+        return Err(Error::CorruptedTable);
+    }
+
+    let (decoded, written) = decode_from_slice(payload, CONFIG)?;
+    let additional = payload.len() - written;
 
     if additional != 0 {
-        return Err(DecodeError::UnexpectedEnd { additional })?;
+        return Err(DecodeError::UnexpectedEnd { additional }.into());
     }
 
     Ok(decoded)
 }
+
+/// Flat layout: after the header, every table as its row length and its number of entries, both `u64`, then its
+/// little endian entries, padded so that every table starts 8 bytes aligned.
+#[cfg(not(target_arch = "wasm32"))]
+fn encode_flat(table: &DataTable) -> Vec<u8> {
+    fn write<T: Entry>(bytes: &mut Vec<u8>, table: &Table<T>) {
+        bytes.extend((table.row_len() as u64).to_le_bytes());
+        bytes.extend((table.as_slice().len() as u64).to_le_bytes());
+
+        for entry in table.as_slice() {
+            entry.write_le(bytes);
+        }
+
+        bytes.resize(bytes.len().next_multiple_of(8), 0);
+    }
+
+    let (moves, pruning) = (&table.move_table, &table.pruning_table);
+    let mut payload = vec![];
+
+    for table in [&moves.co, &moves.eo, &moves.e_combo, &moves.cp, &moves.ep, &moves.e_ep] {
+        write(&mut payload, table);
+    }
+
+    for table in [&pruning.co_e, &pruning.eo_e, &pruning.cp_e, &pruning.ep_e] {
+        write(&mut payload, table);
+    }
+
+    with_header(FLAT_MAGIC, payload)
+}
+
+/// Bytes of a table file in the flat layout, giving tables from the entries in it.
+#[cfg(not(target_arch = "wasm32"))]
+trait FlatSource {
+    fn bytes(&self) -> &[u8];
+    fn table<T: Entry>(&self, offset: usize, len: usize, row_len: usize) -> Table<T>;
+}
+
+/// Copies the entries.
+#[cfg(not(target_arch = "wasm32"))]
+impl FlatSource for [u8] {
+    fn bytes(&self) -> &[u8] {
+        self
+    }
+
+    fn table<T: Entry>(&self, offset: usize, len: usize, row_len: usize) -> Table<T> {
+        let size = std::mem::size_of::<T>();
+        let entries = self[offset..offset + len * size].chunks_exact(size);
+
+        Table::from_vec(entries.map(T::read_le).collect(), row_len)
+    }
+}
+
+/// Reads the entries in place, on little endian hosts.
+#[cfg(not(target_arch = "wasm32"))]
+impl FlatSource for Arc<Mmap> {
+    fn bytes(&self) -> &[u8] {
+        self
+    }
+
+    #[cfg(target_endian = "little")]
+    fn table<T: Entry>(&self, offset: usize, len: usize, row_len: usize) -> Table<T> {
+        Table::mapped(self.clone(), offset, len, row_len)
+    }
+
+    #[cfg(target_endian = "big")]
+    fn table<T: Entry>(&self, offset: usize, len: usize, row_len: usize) -> Table<T> {
+        self[..].table(offset, len, row_len)
+    }
+}
+
+/// Position of the next table of a table file in the flat layout.
+#[cfg(not(target_arch = "wasm32"))]
+struct FlatReader {
+    offset: usize,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl FlatReader {
+    fn field(bytes: &[u8], at: usize) -> Result<usize, Error> {
+        let field = bytes.get(at..at + 8).ok_or(Error::CorruptedTable)?;
+        let field = u64::from_le_bytes(field.try_into().unwrap());
+
+        usize::try_from(field).map_err(|_| Error::CorruptedTable)
+    }
+
+    fn table<T: Entry, S: FlatSource + ?Sized>(&mut self, source: &S) -> Result<Table<T>, Error> {
+        let bytes = source.bytes();
+        let row_len = Self::field(bytes, self.offset)?;
+        let len = Self::field(bytes, self.offset + 8)?;
+        let start = self.offset + 16;
+        let end = len
+            .checked_mul(std::mem::size_of::<T>())
+            .and_then(|size| size.checked_add(start))
+            .ok_or(Error::CorruptedTable)?;
+
+        if end > bytes.len() || !len.is_multiple_of(row_len) {
+            return Err(Error::CorruptedTable);
+        }
+
+        self.offset = end.next_multiple_of(8);
+
+        Ok(source.table(start, len, row_len))
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn decode_flat<S: FlatSource + ?Sized>(source: &S) -> Result<DataTable, Error> {
+    check_header(FLAT_MAGIC, source.bytes())?;
+
+    let mut reader = FlatReader {
+        offset: HEADER_SIZE,
+    };
+
+    let move_table = MoveTable {
+        co: reader.table(source)?,
+        eo: reader.table(source)?,
+        e_combo: reader.table(source)?,
+        cp: reader.table(source)?,
+        ep: reader.table(source)?,
+        e_ep: reader.table(source)?,
+    };
+
+    let pruning_table = PruningTable {
+        co_e: reader.table(source)?,
+        eo_e: reader.table(source)?,
+        cp_e: reader.table(source)?,
+        ep_e: reader.table(source)?,
+    };
+
+    if reader.offset != source.bytes().len() {
+        return Err(Error::CorruptedTable);
+    }
+
+    Ok(DataTable {
+        move_table,
+        pruning_table,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn table() -> DataTable {
+        DataTable {
+            move_table: MoveTable {
+                co: Table::from_vec(vec![1, 2, 0, 3], 2),
+                eo: Table::from_vec(vec![], 18),
+                e_combo: Table::from_vec(vec![4], 1),
+                cp: Table::from_vec(vec![], 10),
+                ep: Table::from_vec(vec![7, 8, 9], 3),
+                e_ep: Table::from_vec(vec![], 10),
+            },
+            pruning_table: PruningTable {
+                co_e: Table::from_vec(vec![0, 1, 2], 3),
+                eo_e: Table::from_vec(vec![], 0),
+                cp_e: Table::from_vec(vec![3], 1),
+                ep_e: Table::from_vec(vec![5, 6], 1),
+            },
+        }
+    }
+
+    #[test]
+    fn test_decode_table() {
+        let encoded = encode_table(&table()).unwrap();
+        let decoded = decode_table(&encoded).unwrap();
+
+        assert_eq!(decoded.move_table.co, table().move_table.co);
+        assert_eq!(decoded.move_table.co[1], [0, 3]);
+        assert_eq!(decoded.pruning_table.ep_e, table().pruning_table.ep_e);
+
+        let mut corrupted = encoded.clone();
+        *corrupted.last_mut().unwrap() ^= 1;
+        assert!(matches!(decode_table(&corrupted), Err(Error::CorruptedTable)));
+        assert!(matches!(decode_table(&encoded[..8]), Err(Error::CorruptedTable)));
+
+        let mut newer = encoded.clone();
+        newer[4..8].copy_from_slice(&(TABLE_VERSION + 1).to_le_bytes());
+        assert!(matches!(decode_table(&newer), Err(Error::IncompatibleTable(_))));
+
+        let mut other_moves = encoded.clone();
+        other_moves[8] ^= 1;
+        assert!(matches!(decode_table(&other_moves), Err(Error::IncompatibleTable(_))));
+
+        let mut bincode = encoded.clone();
+        bincode[..4].copy_from_slice(&[0; 4]);
+        assert!(matches!(decode_table(&bincode), Err(Error::IncompatibleTable(_))));
+    }
+
+    #[test]
+    fn test_read_table() {
+        let path = std::env::temp_dir().join(format!("kewb-test-{}.bin", std::process::id()));
+        save_table(&table(), &path).unwrap();
+
+        let mapped = read_table(&path).unwrap();
+        assert_eq!(mapped.move_table.co, table().move_table.co);
+        assert_eq!(mapped.move_table.ep[0], [7, 8, 9]);
+        assert_eq!(mapped.pruning_table.eo_e, table().pruning_table.eo_e);
+        assert_eq!(mapped.pruning_table.ep_e[1], [6]);
+        assert_eq!(verify_table(&path).unwrap().pruning_table.co_e, table().pruning_table.co_e);
+
+        let mut corrupted = fs::read(&path).unwrap();
+        corrupted[HEADER_SIZE + 16] ^= 1;
+        fs::write(&path, &corrupted).unwrap();
+        assert!(matches!(verify_table(&path), Err(Error::CorruptedTable)));
+        // mapping the file does not read the tables
+        assert!(read_table(&path).is_ok());
+
+        corrupted.truncate(corrupted.len() - 8);
+        fs::write(&path, &corrupted).unwrap();
+        assert!(matches!(read_table(&path), Err(Error::CorruptedTable)));
+
+        fs::write(&path, encode_table(&table()).unwrap()).unwrap();
+        assert_eq!(read_table(&path).unwrap().move_table.ep, table().move_table.ep);
+        assert_eq!(verify_table(&path).unwrap().move_table.ep, table().move_table.ep);
+
+        let flat = encode_flat(&table());
+        let message = decode_table(&flat).err().unwrap().to_string();
+        assert!(message.contains("flat layout"), "{message}");
+
+        fs::remove_file(&path).unwrap();
+    }
+}
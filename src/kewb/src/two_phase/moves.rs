@@ -27,7 +27,7 @@ impl Default for MoveTable {
 }
 
 pub fn get_co_table() -> Table<u16> {
-    let mut co_table = vec![vec![0; 18]; CO_COUNT as usize];
+    let mut co_table = Table::new(CO_COUNT as usize, 18);
 
     for i in 0..CO_COUNT {
         let state = CubieCube {
@@ -45,7 +45,7 @@ pub fn get_co_table() -> Table<u16> {
 }
 
 pub fn get_eo_table() -> Table<u16> {
-    let mut eo_table = vec![vec![0; 18]; EO_COUNT as usize];
+    let mut eo_table = Table::new(EO_COUNT as usize, 18);
 
     for i in 0..EO_COUNT {
         let state = CubieCube {
@@ -63,7 +63,7 @@ pub fn get_eo_table() -> Table<u16> {
 }
 
 pub fn get_e_combo_table() -> Table<u16> {
-    let mut e_combo_table = vec![vec![0; 18]; E_COMBO_COUNT as usize];
+    let mut e_combo_table = Table::new(E_COMBO_COUNT as usize, 18);
     for i in 0..E_COMBO_COUNT {
         let state = CubieCube {
             ep: index_to_e_combo(i),
@@ -80,7 +80,7 @@ pub fn get_e_combo_table() -> Table<u16> {
 }
 
 pub fn get_cp_table() -> Table<u16> {
-    let mut cp_table = vec![vec![0; 10]; CP_COUNT as usize];
+    let mut cp_table = Table::new(CP_COUNT as usize, 10);
 
     for i in 0..CP_COUNT {
         let state = CubieCube {
@@ -98,7 +98,7 @@ pub fn get_cp_table() -> Table<u16> {
 }
 
 pub fn get_ud_ep_table() -> Table<u16> {
-    let mut ep_table = vec![vec![0; 10]; UD_EP_COUNT as usize];
+    let mut ep_table = Table::new(UD_EP_COUNT as usize, 10);
 
     for i in 0..UD_EP_COUNT {
         let state = CubieCube {
@@ -116,7 +116,7 @@ pub fn get_ud_ep_table() -> Table<u16> {
 }
 
 pub fn get_e_ep_table() -> Table<u16> {
-    let mut e_ep_table = vec![vec![0; 10]; E_EP_COUNT as usize];
+    let mut e_ep_table = Table::new(E_EP_COUNT as usize, 10);
 
     for i in 0..E_EP_COUNT {
         let state = CubieCube {
@@ -27,7 +27,7 @@ pub fn get_prune_table(table1: Table<u16>, table2: Table<u16>, moves: &[Move]) -
     let len1 = table1.len();
     let len2 = table2.len();
     let fill_size = len1 * len2;
-    let mut pruning_table = vec![vec![u8::MAX; len2]; len1];
+    let mut pruning_table = Table::from_vec(vec![u8::MAX; fill_size], len2);
     let mut distance = 0;
     let mut filled: usize = 1;
 
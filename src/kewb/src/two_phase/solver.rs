@@ -1,4 +1,7 @@
-use std::fmt;
+use std::{
+    fmt,
+    time::{Duration, Instant},
+};
 
 use crate::cube::{
     cubie::CubieCube,
@@ -164,10 +167,18 @@ impl Solution {
     }
 }
 
+/// Number of searched nodes between two reads of the clock when a timeout is set.
+const CLOCK_INTERVAL: u32 = 1024;
+
 /// Two phase solver.
 pub struct Solver<'a> {
     data_table: &'a DataTable,
     max_length: u8,
+    timeout: Option<Duration>,
+    optimal: bool,
+    deadline: Option<Instant>,
+    timed_out: bool,
+    nodes: u32,
     initial_state: CubieCube,
     solution_phase1: Vec<Move>,
     solution_phase2: Vec<Move>,
@@ -181,12 +192,35 @@ impl<'a> Solver<'a> {
             data_table,
             initial_state: CubieCube::default(),
             max_length,
+            timeout: None,
+            optimal: false,
+            deadline: None,
+            timed_out: false,
+            nodes: 0,
             solution_phase1: vec![],
             solution_phase2: vec![],
             best_solution: None,
         }
     }
 
+    /// Stops searching after `timeout`, returning the best solution found so far if any.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Keeps searching for shorter solutions until the shortest one is found.
+    /// This can take very long, it is meant to be used with a timeout.
+    pub fn with_optimal(mut self, optimal: bool) -> Self {
+        self.optimal = optimal;
+        self
+    }
+
+    /// Whether the last search was stopped by the timeout.
+    pub fn is_timed_out(&self) -> bool {
+        self.timed_out
+    }
+
     /// Resets the solver state.
     pub fn clear(&mut self) {
         self.initial_state = CubieCube::default();
@@ -196,22 +230,83 @@ impl<'a> Solver<'a> {
     }
 
     /// Solves the cube using the two phase algorithm.
+    /// Unsolvable states, such as the ones left by an odd number of M moves, have no solution.
     pub fn solve(&mut self, state: CubieCube) -> Option<Solution> {
+        if !state.is_solvable() {
+            return None;
+        }
+
         self.initial_state = state;
+        self.timed_out = false;
+        self.nodes = 0;
+        self.deadline = self.timeout.map(|timeout| Instant::now() + timeout);
+
+        let max_length = self.max_length;
+        let mut found = false;
+        let mut depth = 0;
+
+        loop {
+            // a phase 1 as long as the best solution can't give a shorter one
+            let limit = if self.optimal && found {
+                self.max_length.saturating_sub(1)
+            } else {
+                self.max_length
+            };
+
+            if depth > limit || self.check_clock() {
+                break;
+            }
+
+            if !self.solve_phase1(Phase1State::from(state), depth) {
+                depth += 1;
+                continue;
+            }
 
-        for depth in 0..=self.max_length {
-            let state = Phase1State::from(state);
-            let found = self.solve_phase1(state, depth);
+            found = true;
 
-            if found {
-                return self.best_solution.clone();
+            if !self.optimal {
+                break;
             }
+
+            // looks for a shorter solution from the same depth
+            self.max_length = self.best_solution.as_ref().map_or(0, |s| s.len() as u8);
+            self.solution_phase1.clear();
+            self.solution_phase2.clear();
         }
 
-        None
+        self.max_length = max_length;
+
+        if found {
+            self.best_solution.clone()
+        } else {
+            None
+        }
+    }
+
+    /// Whether the search is timed out, reading the clock once every `CLOCK_INTERVAL` nodes.
+    fn check_timeout(&mut self) -> bool {
+        self.nodes = self.nodes.wrapping_add(1);
+
+        if self.nodes.is_multiple_of(CLOCK_INTERVAL) {
+            self.check_clock()
+        } else {
+            self.timed_out
+        }
+    }
+
+    fn check_clock(&mut self) -> bool {
+        if let (false, Some(deadline)) = (self.timed_out, self.deadline) {
+            self.timed_out = Instant::now() >= deadline;
+        }
+
+        self.timed_out
     }
 
     fn solve_phase1(&mut self, state: Phase1State, depth: u8) -> bool {
+        if self.check_timeout() {
+            return false;
+        }
+
         if depth == 0 && state.is_solved() {
             let mut cube_state = self.initial_state;
 
@@ -267,6 +362,10 @@ impl<'a> Solver<'a> {
     }
 
     fn solve_phase2(&mut self, state: Phase2State, depth: u8) -> bool {
+        if self.check_timeout() {
+            return false;
+        }
+
         if depth == 0 && state.is_solved() {
             let solution = Solution {
                 phase1: self.solution_phase1.clone(),
@@ -319,7 +418,7 @@ impl<'a> Solver<'a> {
 #[cfg(test)]
 mod test {
     use super::*;
-    use crate::{cube::cubie::SOLVED_CUBIE_CUBE, FaceCube, Move::*};
+    use crate::cube::cubie::SOLVED_CUBIE_CUBE;
 
     #[test]
     fn test_solve() {
@@ -334,4 +433,36 @@ mod test {
 
         assert_eq!(solved_state, SOLVED_CUBIE_CUBE);
     }
+
+    #[test]
+    fn test_solve_optimal() {
+        let scramble = vec![Move::R, Move::U2, Move::F3, Move::L, Move::D];
+        let state = CubieCube::from(&scramble);
+        let table = DataTable::default();
+        let mut solver = Solver::new(&table, 23).with_optimal(true);
+        let solution = solver.solve(state).unwrap();
+
+        assert_eq!(solution.len(), scramble.len());
+        assert_eq!(state.apply_moves(&solution.get_all_moves()), SOLVED_CUBIE_CUBE);
+        assert!(!solver.is_timed_out());
+    }
+
+    #[test]
+    fn test_solve_timeout() {
+        let state = CubieCube::from(&vec![Move::R, Move::U, Move::F]);
+        let table = DataTable::default();
+        let mut solver = Solver::new(&table, 23).with_timeout(Duration::ZERO);
+
+        assert!(solver.solve(state).is_none());
+        assert!(solver.is_timed_out());
+    }
+
+    #[test]
+    fn test_solve_unsolvable() {
+        let table = DataTable::default();
+        let mut solver = Solver::new(&table, 23);
+
+        assert!(solver.solve(CubieCube::from(&vec![Move::M])).is_none());
+        assert!(!solver.is_timed_out());
+    }
 }
@@ -4,14 +4,187 @@ use crate::{
     pruning_table::PruningTable,
 };
 
-use bincode::{Decode, Encode};
+use bincode::{
+    de::{BorrowDecoder, Decoder},
+    enc::Encoder,
+    error::{DecodeError, EncodeError},
+    BorrowDecode, Decode, Encode,
+};
+use std::ops::{Index, IndexMut};
+
+#[cfg(not(target_arch = "wasm32"))]
+use {memmap2::Mmap, std::sync::Arc};
 
 pub const ALL_MOVES: [Move; 18] = [
-    U, U2, U3, D, D2, D3, R, R2, R3, L, L2, L3, F, F2, F2, B, B3, B2,
+    U, U2, U3, D, D2, D3, R, R2, R3, L, L2, L3, F, F2, F3, B, B2, B3,
 ];
 pub const PHASE2_MOVES: [Move; 10] = [U, U2, U3, D, D2, D3, R2, L2, F2, B2];
 
-pub type Table<T> = Vec<Vec<T>>;
+/// Entries of the tables, stored little endian in table files.
+pub trait Entry: Copy + Default {
+    fn write_le(self, bytes: &mut Vec<u8>);
+    fn read_le(bytes: &[u8]) -> Self;
+}
+
+impl Entry for u8 {
+    fn write_le(self, bytes: &mut Vec<u8>) {
+        bytes.push(self);
+    }
+
+    fn read_le(bytes: &[u8]) -> Self {
+        bytes[0]
+    }
+}
+
+impl Entry for u16 {
+    fn write_le(self, bytes: &mut Vec<u8>) {
+        bytes.extend(self.to_le_bytes());
+    }
+
+    fn read_le(bytes: &[u8]) -> Self {
+        u16::from_le_bytes([bytes[0], bytes[1]])
+    }
+}
+
+/// Rows of the same length stored in one flat array, `table[i]` being the i-th row.
+pub struct Table<T> {
+    row_len: usize,
+    data: TableData<T>,
+}
+
+enum TableData<T> {
+    Owned(Vec<T>),
+    /// Entries read in place from a mapped table file, see `fs::read_table`.
+    #[cfg(not(target_arch = "wasm32"))]
+    Mapped {
+        map: Arc<Mmap>,
+        offset: usize,
+        len: usize,
+    },
+}
+
+impl<T: Entry> Table<T> {
+    /// Table of `rows` rows of `row_len` default entries.
+    pub fn new(rows: usize, row_len: usize) -> Self {
+        Self::from_vec(vec![T::default(); rows * row_len], row_len)
+    }
+
+    /// Table of the rows of `row_len` entries of `data`.
+    pub fn from_vec(data: Vec<T>, row_len: usize) -> Self {
+        assert!(data.len().is_multiple_of(row_len), "the entries don't fill the last row");
+
+        Self {
+            row_len,
+            data: TableData::Owned(data),
+        }
+    }
+
+    /// Table of the `len` entries of `map` from `offset`, which must be aligned for `T`.
+    /// The entries are read in place, so they must be little endian like the host.
+    #[cfg(all(not(target_arch = "wasm32"), target_endian = "little"))]
+    pub(crate) fn mapped(map: Arc<Mmap>, offset: usize, len: usize, row_len: usize) -> Self {
+        assert!(offset + len * std::mem::size_of::<T>() <= map.len());
+        assert!((map.as_ptr() as usize + offset).is_multiple_of(std::mem::align_of::<T>()));
+        assert!(len.is_multiple_of(row_len), "the entries don't fill the last row");
+
+        Self {
+            row_len,
+            data: TableData::Mapped { map, offset, len },
+        }
+    }
+}
+
+impl<T> Table<T> {
+    /// Number of rows.
+    pub fn len(&self) -> usize {
+        self.as_slice().len().checked_div(self.row_len).unwrap_or(0)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.as_slice().is_empty()
+    }
+
+    pub fn row_len(&self) -> usize {
+        self.row_len
+    }
+
+    /// Every entry, row after row.
+    pub fn as_slice(&self) -> &[T] {
+        match &self.data {
+            TableData::Owned(data) => data,
+            // SAFETY: `Table::mapped` checked the entries are in the map and aligned, `Entry` is only implemented
+            // by integers, valid for any bytes, and the map is kept alive and never written by the table.
+            #[cfg(not(target_arch = "wasm32"))]
+            TableData::Mapped { map, offset, len } => unsafe {
+                std::slice::from_raw_parts(map.as_ptr().add(*offset) as *const T, *len)
+            },
+        }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &[T]> {
+        self.as_slice().chunks_exact(self.row_len.max(1))
+    }
+}
+
+impl<T> Index<usize> for Table<T> {
+    type Output = [T];
+
+    fn index(&self, index: usize) -> &[T] {
+        &self.as_slice()[index * self.row_len..(index + 1) * self.row_len]
+    }
+}
+
+/// Only tables built in memory can be written, mapped tables are read-only.
+impl<T> IndexMut<usize> for Table<T> {
+    fn index_mut(&mut self, index: usize) -> &mut [T] {
+        let range = index * self.row_len..(index + 1) * self.row_len;
+
+        match &mut self.data {
+            TableData::Owned(data) => &mut data[range],
+            #[cfg(not(target_arch = "wasm32"))]
+            TableData::Mapped { .. } => panic!("mapped tables are read-only"),
+        }
+    }
+}
+
+impl<T: PartialEq> PartialEq for Table<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.row_len == other.row_len && self.as_slice() == other.as_slice()
+    }
+}
+
+impl<T: std::fmt::Debug> std::fmt::Debug for Table<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+/// Tables encode as their row length followed by every entry.
+impl<T: Encode> Encode for Table<T> {
+    fn encode<E: Encoder>(&self, encoder: &mut E) -> Result<(), EncodeError> {
+        self.row_len.encode(encoder)?;
+        self.as_slice().encode(encoder)
+    }
+}
+
+impl<Context, T: Entry + Decode<Context>> Decode<Context> for Table<T> {
+    fn decode<D: Decoder<Context = Context>>(decoder: &mut D) -> Result<Self, DecodeError> {
+        let row_len = usize::decode(decoder)?;
+        let data = Vec::<T>::decode(decoder)?;
+
+        if !data.len().is_multiple_of(row_len) {
+            return Err(DecodeError::Other("the table entries don't fill the last row"));
+        }
+
+        Ok(Self::from_vec(data, row_len))
+    }
+}
+
+impl<'de, Context, T: Entry + Decode<Context>> BorrowDecode<'de, Context> for Table<T> {
+    fn borrow_decode<D: BorrowDecoder<'de, Context = Context>>(decoder: &mut D) -> Result<Self, DecodeError> {
+        Self::decode(decoder)
+    }
+}
 
 /// Contains the move and prunning table used by the two-phase algorithm
 #[derive(Default, Encode, Decode)]
@@ -19,3 +192,20 @@ pub struct DataTable {
     pub move_table: MoveTable,
     pub pruning_table: PruningTable,
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_moves() {
+        for (i, m) in ALL_MOVES.iter().enumerate() {
+            assert!(!ALL_MOVES[i + 1..].contains(m), "{m} is repeated");
+        }
+
+        for (i, m) in PHASE2_MOVES.iter().enumerate() {
+            assert!(ALL_MOVES.contains(m));
+            assert!(!PHASE2_MOVES[i + 1..].contains(m), "{m} is repeated");
+        }
+    }
+}
@@ -12,6 +12,12 @@ mod actions;
 mod ui;
 mod settings;
 mod solver;
+mod playback;
+mod puzzle;
+mod notation;
+mod recorder;
+mod session;
+mod storage;
 
 fn main() {
     App::new()
@@ -21,16 +27,25 @@ fn main() {
         EguiPlugin,
     ))
     .insert_resource(CubeInfo::default())
+    .insert_resource(camera::OrbitCamera::default())
     .insert_resource(ActionStatus { 
-        angle_to_process: 0.0,
+        progress: 0.0,
         action_queue: VecDeque::new(),
         cur_action: None,
+        parallel_actions: Vec::new(),
+        last_moves: Vec::new(),
+        history: Vec::new(),
         drag_start: None,
         drag_end: None,
         selected_entity: None,
         computing_solution: false,
     })
-    .insert_resource(settings::Settings::default())
+    .insert_resource(settings::Settings::load())
+    .insert_resource(ui::Tutorial::default())
+    .insert_resource(ui::SolveResult::default())
+    .insert_resource(ui::LastLayerCase::default())
+    .insert_resource(playback::Playback::default())
+    .insert_resource(recorder::Recorder::default())
     .add_systems(
         Startup, 
         (
@@ -42,8 +57,19 @@ fn main() {
         Update, 
         (
             camera::handle_view,
+            camera::handle_touch,
+            camera::update_camera,
+            actions::handle_keyboard,
             actions::frame_handler,
+            cube::recolor_cubes,
+            playback::playback_handler,
+            recorder::recorder_handler,
             ui::update_ui,
+            ui::update_playback_ui,
+            ui::update_net_ui,
+            ui::update_recorder_ui,
+            ui::update_session_ui,
+            settings::save_settings,
         )
     )
     .run();
@@ -0,0 +1,314 @@
+//! Conversions between `Movement`s and `kewb` moves, and the notation of any sequence of movements.
+
+use kewb::Move::{self, *};
+use crate::puzzle::{Direction, LayerRange, Movement, RotateAxis};
+
+/// Layers of the cube `kewb` solves, `Move`s are 3x3 moves.
+const KEWB_LAYERS: u32 = 3;
+
+/// Moves of a layer of the 3x3, turning clockwise, double and counter-clockwise, and whether the face clockwise
+/// turn is a `Direction::CounterClockwise` movement. The M slice turns like L.
+fn layer_moves(axis: RotateAxis, layer: u32) -> Option<([Move; 3], bool)> {
+    match (axis, layer) {
+        (RotateAxis::Y, 2) => Some(([U, U2, U3], false)),
+        (RotateAxis::Y, 0) => Some(([D, D2, D3], true)),
+        (RotateAxis::X, 2) => Some(([R, R2, R3], false)),
+        (RotateAxis::X, 0) => Some(([L, L2, L3], true)),
+        (RotateAxis::X, 1) => Some(([M, M2, M3], true)),
+        (RotateAxis::Z, 2) => Some(([F, F2, F3], false)),
+        (RotateAxis::Z, 0) => Some(([B, B2, B3], true)),
+        _ => None,
+    }
+}
+
+impl From<Move> for Movement {
+    fn from(value: Move) -> Self {
+        let (axis, layer) = match value {
+            U | U2 | U3 => (RotateAxis::Y, 2),
+            D | D2 | D3 => (RotateAxis::Y, 0),
+            R | R2 | R3 => (RotateAxis::X, 2),
+            L | L2 | L3 => (RotateAxis::X, 0),
+            M | M2 | M3 => (RotateAxis::X, 1),
+            F | F2 | F3 => (RotateAxis::Z, 2),
+            B | B2 | B3 => (RotateAxis::Z, 0),
+        };
+        let (moves, flipped) = layer_moves(axis, layer).unwrap();
+        let kind = moves.iter().position(|m| *m == value).unwrap();
+        let counter_clockwise = (kind == 2) != flipped;
+
+        Movement {
+            axis,
+            layers: LayerRange::single(layer),
+            direction: if counter_clockwise { Direction::CounterClockwise } else { Direction::Clockwise },
+            turns: if kind == 1 { 2 } else { 1 },
+        }
+    }
+}
+
+/// Single layer movements of a 3x3, including the M slice, have a `Move`.
+/// Other movements, and movements turning a multiple of four quarter turns, are given back as the error.
+impl TryFrom<Movement> for Move {
+    type Error = Movement;
+
+    fn try_from(value: Movement) -> Result<Self, Self::Error> {
+        if value.layers.start != value.layers.end || value.layers.end >= KEWB_LAYERS {
+            return Err(value);
+        }
+        let (moves, flipped) = layer_moves(value.axis, value.layers.start).ok_or(value)?;
+        let counter_clockwise = (value.direction == Direction::CounterClockwise) != flipped;
+
+        match (value.turns % 4, counter_clockwise) {
+            (2, _) => Ok(moves[1]),
+            (1, false) | (3, true) => Ok(moves[0]),
+            (1, true) | (3, false) => Ok(moves[2]),
+            _ => Err(value),
+        }
+    }
+}
+
+/// Faces turning with a `Direction::Clockwise` movement, from the last layer of an axis, and turning the other way
+/// from its first layer.
+fn faces(axis: RotateAxis) -> (char, char) {
+    match axis {
+        RotateAxis::X => ('R', 'L'),
+        RotateAxis::Y => ('U', 'D'),
+        RotateAxis::Z => ('F', 'B'),
+    }
+}
+
+/// Notation of a movement on a cube of `layers` layers.
+/// Whole cube rotations are x, y and z, the middle slices of odd cubes M, E and S, turns reaching an outer face
+/// are named after it with a w for wide turns and the number of layers past two, as in 3Rw, and inner slices are
+/// numbered from the nearest face, as in 2R or 2-3Rw. A movement of layers outside the cube is given back as the error.
+pub fn movement_notation(movement: &Movement, layers: u32) -> Result<String, Movement> {
+    if !movement.layers.fits(layers) {
+        return Err(*movement);
+    }
+    let last = layers - 1;
+    let LayerRange { start, end } = movement.layers;
+    let (positive, negative) = faces(movement.axis);
+
+    let (name, follows_positive) = if start == 0 && end == last {
+        let name = match movement.axis {
+            RotateAxis::X => "x",
+            RotateAxis::Y => "y",
+            RotateAxis::Z => "z",
+        };
+        (name.to_string(), true)
+    } else if layers % 2 == 1 && start == last / 2 && end == last / 2 {
+        match movement.axis {
+            RotateAxis::X => ("M".to_string(), false),
+            RotateAxis::Y => ("E".to_string(), false),
+            RotateAxis::Z => ("S".to_string(), true),
+        }
+    } else {
+        // layers counted from the nearest face, 1 being the face itself
+        let from_positive = start + end > last;
+        let (face, near, far) = if from_positive {
+            (positive, last - end + 1, last - start + 1)
+        } else {
+            (negative, start + 1, end + 1)
+        };
+        let name = match (near, far) {
+            (1, 1) => face.to_string(),
+            (1, 2) => format!("{}w", face),
+            (1, _) => format!("{}{}w", far, face),
+            (_, _) if near == far => format!("{}{}", near, face),
+            (_, _) => format!("{}-{}{}w", near, far, face),
+        };
+        (name, from_positive)
+    };
+
+    let counter_clockwise = (movement.direction == Direction::CounterClockwise) == follows_positive;
+    let suffix = match (movement.turns % 4, counter_clockwise) {
+        (2, _) => "2",
+        (1, true) | (3, false) => "'",
+        _ => "",
+    };
+
+    Ok(format!("{}{}", name, suffix))
+}
+
+/// Notation of a sequence of movements, separated by spaces, or the first movement outside the cube.
+pub fn notation(movements: &[Movement], layers: u32) -> Result<String, Movement> {
+    Ok(movements.iter().map(|m| movement_notation(m, layers)).collect::<Result<Vec<_>, _>>()?.join(" "))
+}
+
+/// Parses the notation of a movement on a cube of `layers` layers, as written by `movement_notation`.
+/// Lowercase face letters are also read as wide turns, and 2' as a double turn.
+pub fn parse_movement(s: &str, layers: u32) -> Option<Movement> {
+    let last = layers - 1;
+    let digits = s.find(|c: char| !c.is_ascii_digit() && c != '-')?;
+    let (prefix, rest) = s.split_at(digits);
+    let mut chars = rest.chars();
+    let letter = chars.next()?;
+    let rest = chars.as_str();
+    let (wide, suffix) = match rest.strip_prefix('w') {
+        Some(suffix) => (true, suffix),
+        None => (letter.is_ascii_lowercase() && !"xyz".contains(letter), rest),
+    };
+    let (turns, prime) = match suffix {
+        "" => (1, false),
+        "'" => (1, true),
+        "2" | "2'" => (2, false),
+        _ => return None,
+    };
+
+    let (axis, layers, follows_positive) = match letter {
+        'x' | 'y' | 'z' if prefix.is_empty() && !wide => {
+            let axis = match letter {
+                'x' => RotateAxis::X,
+                'y' => RotateAxis::Y,
+                _ => RotateAxis::Z,
+            };
+            (axis, LayerRange::all(layers), true)
+        },
+        'M' | 'E' | 'S' if prefix.is_empty() && !wide && layers % 2 == 1 => {
+            let (axis, follows_positive) = match letter {
+                'M' => (RotateAxis::X, false),
+                'E' => (RotateAxis::Y, false),
+                _ => (RotateAxis::Z, true),
+            };
+            (axis, LayerRange::single(last / 2), follows_positive)
+        },
+        _ => {
+            let face = letter.to_ascii_uppercase();
+            let (axis, from_positive) = [RotateAxis::X, RotateAxis::Y, RotateAxis::Z].into_iter().find_map(|axis| {
+                let (positive, negative) = faces(axis);
+                if face == positive {
+                    Some((axis, true))
+                } else if face == negative {
+                    Some((axis, false))
+                } else {
+                    None
+                }
+            })?;
+
+            // layers counted from the face, 1 being the face itself
+            let (near, far) = match (prefix.split_once('-'), wide) {
+                (Some((near, far)), true) => (near.parse().ok()?, far.parse().ok()?),
+                (Some(_), false) => return None,
+                (None, _) if prefix.is_empty() => (1, if wide { 2 } else { 1 }),
+                (None, true) => (1, prefix.parse().ok()?),
+                (None, false) => {
+                    let n = prefix.parse().ok()?;
+                    (n, n)
+                },
+            };
+            if near < 1 || near > far || far > layers {
+                return None;
+            }
+
+            let layers = if from_positive {
+                LayerRange { start: last + 1 - far, end: last + 1 - near }
+            } else {
+                LayerRange { start: near - 1, end: far - 1 }
+            };
+            (axis, layers, from_positive)
+        },
+    };
+
+    Some(Movement {
+        axis,
+        layers,
+        direction: if prime == follows_positive { Direction::CounterClockwise } else { Direction::Clockwise },
+        turns,
+    })
+}
+
+/// Parses a sequence of movements separated by whitespace, failing on the first unknown movement.
+pub fn parse_notation(s: &str, layers: u32) -> Option<Vec<Movement>> {
+    s.split_whitespace().map(|m| parse_movement(m, layers)).collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[rustfmt::skip]
+    const ALL_MOVES: [Move; 21] = [
+        U, U2, U3, D, D2, D3, R, R2, R3, L, L2, L3, F, F2, F3, B, B2, B3, M, M2, M3,
+    ];
+
+    fn turn(axis: RotateAxis, start: u32, end: u32, direction: Direction, turns: u32) -> Movement {
+        Movement { axis, layers: LayerRange { start, end }, direction, turns }
+    }
+
+    #[test]
+    fn test_move_conversion() {
+        for m in ALL_MOVES {
+            let movement = Movement::from(m);
+            assert_eq!(Move::try_from(movement), Ok(m));
+            assert_eq!(movement_notation(&movement, 3), Ok(m.to_string()));
+        }
+
+        // three quarter turns are the inverse move
+        let r3 = Movement { turns: 3, ..Movement::from(R) };
+        assert_eq!(Move::try_from(r3), Ok(R3));
+
+        let wide = turn(RotateAxis::X, 1, 2, Direction::Clockwise, 1);
+        assert_eq!(Move::try_from(wide), Err(wide));
+        let inner = turn(RotateAxis::Y, 1, 1, Direction::Clockwise, 1);
+        assert_eq!(Move::try_from(inner), Err(inner));
+    }
+
+    #[test]
+    fn test_notation() {
+        use Direction::*;
+        use RotateAxis::*;
+
+        let moves = [
+            Movement::rotation(X, false, 3),
+            Movement::rotation(Y, true, 3),
+            turn(Y, 1, 1, Clockwise, 1),
+            turn(Z, 1, 1, Clockwise, 2),
+            turn(X, 1, 2, CounterClockwise, 1),
+            turn(Y, 0, 1, Clockwise, 1),
+        ];
+        assert_eq!(notation(&moves, 3), Ok("x y' E' S2 Rw' Dw'".to_string()));
+
+        let moves = [
+            turn(X, 0, 2, Clockwise, 1),
+            turn(X, 4, 4, Clockwise, 1),
+            turn(X, 1, 1, Clockwise, 1),
+            turn(Z, 1, 2, CounterClockwise, 1),
+            turn(Z, 3, 4, Clockwise, 1),
+            turn(Y, 0, 4, Clockwise, 3),
+        ];
+        assert_eq!(notation(&moves, 5), Ok("3Lw' R 2L' 2-3Bw Fw y'".to_string()));
+
+        // layers past the cube have no notation
+        let outside = turn(X, 2, 2, Clockwise, 1);
+        assert_eq!(movement_notation(&outside, 2), Err(outside));
+        assert_eq!(notation(&[turn(X, 1, 1, Clockwise, 1), outside], 2), Err(outside));
+        let reversed = turn(X, 1, 0, Clockwise, 1);
+        assert_eq!(movement_notation(&reversed, 3), Err(reversed));
+    }
+
+    #[test]
+    fn test_parse_notation() {
+        for layers in [2, 3, 4, 5] {
+            let last = layers - 1;
+            for axis in [RotateAxis::X, RotateAxis::Y, RotateAxis::Z] {
+                for start in 0..layers {
+                    for end in start..layers {
+                        for (direction, turns) in [(Direction::Clockwise, 1), (Direction::CounterClockwise, 1), (Direction::Clockwise, 2)] {
+                            let m = turn(axis, start, end, direction, turns);
+                            let text = movement_notation(&m, layers).unwrap();
+                            let parsed = parse_movement(&text, layers);
+                            // a double turn is the same either way
+                            let parsed = parsed.map(|p| if turns == 2 { Movement { direction, ..p } } else { p });
+                            assert_eq!(parsed, Some(m), "{} on {} layers", text, layers);
+                        }
+                    }
+                }
+            }
+            assert_eq!(parse_movement("R", layers), Some(turn(RotateAxis::X, last, last, Direction::Clockwise, 1)));
+        }
+
+        assert_eq!(parse_notation("r U2' l'", 3), parse_notation("Rw U2 Lw'", 3));
+        assert_eq!(parse_notation("R U Q", 3), None);
+        assert_eq!(parse_movement("4Rw", 3), None);
+        assert_eq!(parse_movement("M", 4), None);
+    }
+}
@@ -0,0 +1,139 @@
+use bevy::prelude::*;
+use crate::{actions::{is_parallel, ActionStatus}, puzzle::{Direction, Movement}, settings::Settings};
+
+/// Speed used while jumping to a move picked with the slider or in the notation.
+const SCRUB_SPEED: f32 = 30.0;
+
+/// A solution or a reconstruction played move by move, which can be paused, stepped through and scrubbed.
+#[derive(Resource)]
+pub struct Playback {
+    pub moves: Vec<Movement>,
+    /// Number of moves already applied to the cube.
+    pub position: usize,
+    /// Position the cube is brought to, one move at a time.
+    pub target: usize,
+    /// Layer rotation speed of the playback, overriding `Settings::layer_rotation_speed`.
+    pub speed: f32,
+    /// Whether the target was picked with `seek`, it is then reached at `SCRUB_SPEED`.
+    scrubbing: bool,
+    /// Whether the move being animated comes from the playback.
+    driving: bool,
+}
+
+impl Default for Playback {
+    fn default() -> Self {
+        Playback {
+            moves: vec![],
+            position: 0,
+            target: 0,
+            speed: 2.0,
+            scrubbing: false,
+            driving: false,
+        }
+    }
+}
+
+impl Playback {
+    /// Loads new moves, paused before the first one.
+    pub fn load(&mut self, moves: Vec<Movement>) {
+        self.moves = moves;
+        self.position = 0;
+        self.target = 0;
+        self.scrubbing = false;
+    }
+
+    pub fn clear(&mut self) {
+        self.load(vec![]);
+    }
+
+    pub fn is_loaded(&self) -> bool {
+        !self.moves.is_empty()
+    }
+
+    pub fn is_playing(&self) -> bool {
+        !self.scrubbing && self.target > self.position
+    }
+
+    pub fn play(&mut self) {
+        self.target = self.moves.len();
+        self.scrubbing = false;
+    }
+
+    pub fn pause(&mut self) {
+        self.target = self.position;
+    }
+
+    pub fn step_forward(&mut self) {
+        self.target = usize::min(self.position + 1, self.moves.len());
+        self.scrubbing = false;
+    }
+
+    pub fn step_back(&mut self) {
+        self.target = self.position.saturating_sub(1);
+        self.scrubbing = false;
+    }
+
+    /// Brings the cube to the given position quickly.
+    pub fn seek(&mut self, position: usize) {
+        self.target = usize::min(position, self.moves.len());
+        self.scrubbing = true;
+    }
+
+    /// Layer rotation speed to use instead of the settings while the playback drives the cube.
+    pub fn rotation_speed(&self) -> Option<f32> {
+        match (self.driving, self.scrubbing) {
+            (false, _) => None,
+            (true, false) => Some(self.speed),
+            (true, true) => Some(SCRUB_SPEED),
+        }
+    }
+}
+
+fn inverse_movement(m: Movement) -> Movement {
+    Movement {
+        direction: match m.direction {
+            Direction::Clockwise => Direction::CounterClockwise,
+            Direction::CounterClockwise => Direction::Clockwise,
+        },
+        ..m
+    }
+}
+
+/// Queues the next moves towards the target. With `Settings::overlap_parallel_moves`, the following moves parallel
+/// to the next one are queued along with it so that they are animated together, as keyboard moves are.
+pub fn playback_handler(
+    mut playback: ResMut<Playback>,
+    mut status: ResMut<ActionStatus>,
+    settings: Res<Settings>,
+) {
+    if !status.action_queue.is_empty() || status.cur_action.is_some() || status.computing_solution {
+        return;
+    }
+
+    if playback.position == playback.target {
+        playback.driving = false;
+        playback.scrubbing = false;
+        return;
+    }
+
+    let mut queued: Vec<Movement> = vec![];
+    while playback.position != playback.target {
+        let m = if playback.position < playback.target {
+            playback.moves[playback.position]
+        } else {
+            inverse_movement(playback.moves[playback.position - 1])
+        };
+        if !queued.is_empty() && !(settings.overlap_parallel_moves && is_parallel(&m, &queued)) {
+            break;
+        }
+
+        queued.push(m);
+        if playback.position < playback.target {
+            playback.position += 1;
+        } else {
+            playback.position -= 1;
+        }
+    }
+    status.action_queue.extend(queued);
+    playback.driving = true;
+}
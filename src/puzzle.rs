@@ -0,0 +1,332 @@
+//! Logical state of the puzzle, independent of the entities rendering it.
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+pub enum Direction {
+    // facing the negative direction
+    Clockwise,
+    CounterClockwise,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+pub enum RotateAxis {
+    X, Y, Z
+}
+
+/// Layers `start..=end` along an axis.
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+pub struct LayerRange {
+    pub start: u32,
+    pub end: u32,
+}
+
+impl LayerRange {
+    pub fn single(layer: u32) -> Self {
+        LayerRange { start: layer, end: layer }
+    }
+
+    /// Every layer, turning the whole cube.
+    pub fn all(layers: u32) -> Self {
+        LayerRange { start: 0, end: layers - 1 }
+    }
+
+    /// Two layers or more, from the outer face nearest to `layer` up to `layer`.
+    pub fn wide(layer: u32, layers: u32) -> Self {
+        if layer * 2 < layers {
+            LayerRange { start: 0, end: u32::max(layer, 1) }
+        } else {
+            LayerRange { start: u32::min(layer, layers - 2), end: layers - 1 }
+        }
+    }
+
+    pub fn contains(&self, layer: i32) -> bool {
+        self.start as i32 <= layer && layer <= self.end as i32
+    }
+
+    pub fn overlaps(&self, other: &LayerRange) -> bool {
+        self.start <= other.end && other.start <= self.end
+    }
+
+    /// Whether the range is a valid range of layers of a cube of `layers` layers.
+    pub fn fits(&self, layers: u32) -> bool {
+        self.start <= self.end && self.end < layers
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+pub struct Movement {
+    pub axis: RotateAxis,
+    pub layers: LayerRange,
+    pub direction: Direction,
+    /// Number of quarter turns, 1, 2 or 3.
+    pub turns: u32,
+}
+
+impl Movement {
+    /// Whole cube rotation, x, y and z turn like R, U and F.
+    pub fn rotation(axis: RotateAxis, inverse: bool, layers: u32) -> Self {
+        Movement {
+            axis,
+            layers: LayerRange::all(layers),
+            direction: if inverse { Direction::CounterClockwise } else { Direction::Clockwise },
+            turns: 1,
+        }
+    }
+
+    /// Coordinates of a cubie at `coord` after the movement, if it is in one of the turned layers.
+    pub fn turn_coord(&self, coord: [i32; 3], layers: u32) -> [i32; 3] {
+        if !self.layers.contains(coord[self.axis as usize]) {
+            return coord;
+        }
+        (0..self.turns).fold(coord, |c, _| self.turn_coord_quarter(c, layers))
+    }
+
+    fn turn_coord_quarter(&self, [x, y, z]: [i32; 3], layers: u32) -> [i32; 3] {
+        let m = layers as i32 - 1;
+        match (self.axis, self.direction) {
+            (RotateAxis::X, Direction::Clockwise) => [x, z, m - y],
+            (RotateAxis::X, Direction::CounterClockwise) => [x, m - z, y],
+            (RotateAxis::Y, Direction::Clockwise) => [m - z, y, x],
+            (RotateAxis::Y, Direction::CounterClockwise) => [z, y, m - x],
+            (RotateAxis::Z, Direction::Clockwise) => [y, m - x, z],
+            (RotateAxis::Z, Direction::CounterClockwise) => [m - y, x, z],
+        }
+    }
+}
+
+/// Sides of the cube, in the facelet order of `kewb`'s `FaceCube`.
+/// U is +Y, R is +X and F is +Z.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Serialize, Deserialize)]
+pub enum Side {
+    U, R, F, D, L, B
+}
+
+impl Side {
+    pub const ALL: [Side; 6] = [Side::U, Side::R, Side::F, Side::D, Side::L, Side::B];
+
+    pub fn letter(&self) -> char {
+        match self {
+            Side::U => 'U',
+            Side::R => 'R',
+            Side::F => 'F',
+            Side::D => 'D',
+            Side::L => 'L',
+            Side::B => 'B',
+        }
+    }
+
+    /// Side the facelet faces after a quarter turn around `axis`.
+    fn turn(&self, axis: RotateAxis, direction: Direction) -> Side {
+        use Side::*;
+        let cycle = match axis {
+            RotateAxis::X => [F, U, B, D],
+            RotateAxis::Y => [R, F, L, B],
+            RotateAxis::Z => [L, U, R, D],
+        };
+        match cycle.iter().position(|s| s == self) {
+            Some(i) if direction == Direction::Clockwise => cycle[(i + 1) % 4],
+            Some(i) => cycle[(i + 3) % 4],
+            None => *self,
+        }
+    }
+}
+
+/// Colours of a NxN cube as the side each facelet belongs to when solved.
+/// Facelets are stored side by side in the order of `Side`, each side row by row as in the net drawn in `solver.rs`.
+/// Coordinates go from 0 to N - 1 along X (L to R), Y (D to U) and Z (B to F), like the `Cube` entities.
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct PuzzleState {
+    layers: u32,
+    facelets: Vec<Side>,
+}
+
+impl PuzzleState {
+    pub fn new(layers: u32) -> Self {
+        let n = (layers * layers) as usize;
+        PuzzleState {
+            layers,
+            facelets: Side::ALL.iter().flat_map(|s| std::iter::repeat_n(*s, n)).collect(),
+        }
+    }
+
+    pub fn layers(&self) -> u32 {
+        self.layers
+    }
+
+    pub fn facelets(&self) -> &[Side] {
+        &self.facelets
+    }
+
+    /// Whether there are N² facelets of each side, a deserialized state may not have them.
+    pub fn is_valid(&self) -> bool {
+        let n = (self.layers * self.layers) as usize;
+        self.facelets.len() == 6 * n && Side::ALL.iter().all(|s| self.facelets.iter().filter(|f| *f == s).count() == n)
+    }
+
+    pub fn is_solved(&self) -> bool {
+        let n = (self.layers * self.layers) as usize;
+        self.facelets.chunks(n).all(|side| side.iter().all(|s| *s == side[0]))
+    }
+
+    /// Colour of the facelet of the cubie at `coord` facing `side`.
+    pub fn get(&self, coord: [i32; 3], side: Side) -> Side {
+        self.facelets[self.index(coord, side)]
+    }
+
+    pub fn apply(&mut self, movement: &Movement) {
+        for _ in 0..movement.turns {
+            self.apply_quarter(movement);
+        }
+    }
+
+    pub fn apply_all(&mut self, movements: &[Movement]) {
+        for m in movements {
+            self.apply(m);
+        }
+    }
+
+    fn apply_quarter(&mut self, movement: &Movement) {
+        let mut facelets = self.facelets.clone();
+
+        for i in 0..self.facelets.len() {
+            let (coord, side) = self.position(i);
+            if movement.layers.contains(coord[movement.axis as usize]) {
+                let target = self.index(movement.turn_coord_quarter(coord, self.layers), side.turn(movement.axis, movement.direction));
+                facelets[target] = self.facelets[i];
+            }
+        }
+
+        self.facelets = facelets;
+    }
+
+    fn index(&self, [x, y, z]: [i32; 3], side: Side) -> usize {
+        let n = self.layers as i32;
+        let m = n - 1;
+        let (row, col) = match side {
+            Side::U => (z, x),
+            Side::R => (m - y, m - z),
+            Side::F => (m - y, x),
+            Side::D => (m - z, x),
+            Side::L => (m - y, z),
+            Side::B => (m - y, m - x),
+        };
+        side as usize * (n * n) as usize + (row * n + col) as usize
+    }
+
+    fn position(&self, index: usize) -> ([i32; 3], Side) {
+        let n = self.layers as usize;
+        let m = n as i32 - 1;
+        let side = Side::ALL[index / (n * n)];
+        let row = (index % (n * n) / n) as i32;
+        let col = (index % n) as i32;
+        let coord = match side {
+            Side::U => [col, m, row],
+            Side::R => [m, m - row, m - col],
+            Side::F => [col, m - row, m],
+            Side::D => [col, 0, m - row],
+            Side::L => [0, m - row, col],
+            Side::B => [m - col, m - row, 0],
+        };
+        (coord, side)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn layer_turn(axis: RotateAxis, layer: u32, direction: Direction, turns: u32) -> Movement {
+        Movement { axis, layers: LayerRange::single(layer), direction, turns }
+    }
+
+    fn inverse(m: &Movement) -> Movement {
+        let direction = match m.direction {
+            Direction::Clockwise => Direction::CounterClockwise,
+            Direction::CounterClockwise => Direction::Clockwise,
+        };
+        Movement { direction, ..*m }
+    }
+
+    /// A few turns on every axis, in both directions and on inner layers.
+    fn scramble(layers: u32) -> Vec<Movement> {
+        let mut moves = vec![];
+        for (i, axis) in [RotateAxis::X, RotateAxis::Y, RotateAxis::Z, RotateAxis::Y, RotateAxis::X].into_iter().enumerate() {
+            let direction = if i % 2 == 0 { Direction::Clockwise } else { Direction::CounterClockwise };
+            moves.push(layer_turn(axis, i as u32 % layers, direction, 1 + i as u32 % 3));
+            moves.push(Movement { layers: LayerRange::wide(layers - 2, layers), ..layer_turn(axis, 0, direction, 1) });
+        }
+        moves
+    }
+
+    #[test]
+    fn test_turn_coord() {
+        for layers in [2, 3, 4] {
+            let m = layers as i32 - 1;
+            for axis in [RotateAxis::X, RotateAxis::Y, RotateAxis::Z] {
+                let turn = Movement { layers: LayerRange::all(layers), ..layer_turn(axis, 0, Direction::Clockwise, 1) };
+                for coord in [[0, 0, 0], [m, 0, 0], [0, m, 1], [1, 1, m]] {
+                    let turned = turn.turn_coord(coord, layers);
+                    assert_eq!(turned[axis as usize], coord[axis as usize]);
+                    assert_eq!(inverse(&turn).turn_coord(turned, layers), coord);
+                    assert_eq!(Movement { turns: 4, ..turn }.turn_coord(coord, layers), coord);
+                }
+            }
+        }
+
+        // cubies outside the turned layers stay in place
+        assert_eq!(layer_turn(RotateAxis::X, 2, Direction::Clockwise, 1).turn_coord([0, 0, 0], 3), [0, 0, 0]);
+    }
+
+    #[test]
+    fn test_quarter_turns() {
+        // U moves the front face to the left
+        let mut state = PuzzleState::new(3);
+        state.apply(&layer_turn(RotateAxis::Y, 2, Direction::Clockwise, 1));
+        assert_eq!(state.get([0, 2, 2], Side::L), Side::F);
+        assert_eq!(state.get([0, 2, 2], Side::U), Side::U);
+        assert_eq!(state.get([0, 1, 2], Side::L), Side::L);
+
+        let half = layer_turn(RotateAxis::Z, 0, Direction::CounterClockwise, 2);
+        let mut twice = PuzzleState::new(3);
+        twice.apply_all(&[Movement { turns: 1, ..half }, Movement { turns: 1, ..half }]);
+        let mut once = PuzzleState::new(3);
+        once.apply(&half);
+        assert_eq!(once, twice);
+    }
+
+    #[test]
+    fn test_inverse() {
+        for layers in [2, 3, 4, 5] {
+            let moves = scramble(layers);
+            let mut state = PuzzleState::new(layers);
+            state.apply_all(&moves);
+            assert!(!state.is_solved());
+
+            let inverses: Vec<Movement> = moves.iter().rev().map(inverse).collect();
+            state.apply_all(&inverses);
+            assert_eq!(state, PuzzleState::new(layers));
+        }
+    }
+
+    #[test]
+    fn test_rotation() {
+        let mut state = PuzzleState::new(3);
+        for axis in [RotateAxis::X, RotateAxis::Y, RotateAxis::Z] {
+            state.apply(&Movement::rotation(axis, false, 3));
+            assert!(state.is_solved());
+        }
+        assert_eq!(state.get([1, 2, 1], Side::U), Side::D);
+
+        // x moves the front face up
+        let mut state = PuzzleState::new(3);
+        state.apply(&Movement::rotation(RotateAxis::X, false, 3));
+        assert_eq!(state.get([1, 2, 1], Side::U), Side::F);
+
+        // a slice turn moves the centers
+        let mut state = PuzzleState::new(3);
+        state.apply(&layer_turn(RotateAxis::X, 1, Direction::CounterClockwise, 1));
+        assert!(!state.is_solved());
+        assert_eq!(state.get([1, 2, 1], Side::U), Side::B);
+    }
+}
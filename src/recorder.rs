@@ -0,0 +1,216 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use crate::{actions::ActionStatus, cube::Puzzle, notation::{movement_notation, notation, parse_movement, parse_notation}, puzzle::{Movement, PuzzleState}, settings::SolveMethod, solver::detect_phases};
+
+/// A movement of a solve, made `time` seconds after the first one.
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+pub struct TimedMovement {
+    pub movement: Movement,
+    pub time: f32,
+}
+
+/// A phase of a solve, with its number of movements and its duration in seconds.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Split {
+    pub name: &'static str,
+    pub moves: usize,
+    pub time: f32,
+}
+
+/// A solve of a scramble applied to a solved cube, with the time of every movement.
+#[derive(Clone, PartialEq, Debug, Default, Serialize, Deserialize)]
+pub struct Reconstruction {
+    pub scramble: Vec<Movement>,
+    pub moves: Vec<TimedMovement>,
+}
+
+impl Reconstruction {
+    pub fn movements(&self) -> Vec<Movement> {
+        self.moves.iter().map(|m| m.movement).collect()
+    }
+
+    /// Time of the last movement, the first one starting the timer.
+    pub fn duration(&self) -> f32 {
+        self.moves.last().map_or(0.0, |m| m.time)
+    }
+
+    /// Whether every movement is on a cube of `layers` layers and every time is a number of seconds.
+    pub fn is_valid(&self, layers: u32) -> bool {
+        self.scramble.iter().all(|m| m.layers.fits(layers))
+            && self.moves.iter().all(|m| m.movement.layers.fits(layers) && m.time.is_finite() && m.time >= 0.0)
+    }
+
+    /// Turns per second, every movement counting as a turn.
+    pub fn tps(&self) -> f32 {
+        if self.duration() > 0.0 {
+            self.moves.len() as f32 / self.duration()
+        } else {
+            0.0
+        }
+    }
+
+    /// Phases of the solve with `method`, see `detect_phases`.
+    pub fn splits(&self, layers: u32, method: SolveMethod) -> Vec<Split> {
+        let mut start = PuzzleState::new(layers);
+        start.apply_all(&self.scramble);
+
+        let mut previous = (0, 0.0);
+        detect_phases(method, &start, &self.movements()).into_iter().map(|(name, end)| {
+            let time = if end == 0 { 0.0 } else { self.moves[end - 1].time };
+            let split = Split { name, moves: end - previous.0, time: time - previous.1 };
+            previous = (end, time);
+            split
+        }).collect()
+    }
+
+    /// Text of the reconstruction: the scramble, the solve with the time of each movement after an @, then the
+    /// totals and the splits of `method`. Only the scramble and the solve are read back by `import`.
+    /// A movement outside a cube of `layers` layers is given back as the error.
+    pub fn export(&self, layers: u32, method: SolveMethod) -> Result<String, Movement> {
+        let solve = self.moves.iter()
+            .map(|m| movement_notation(&m.movement, layers).map(|name| format!("{}@{:.2}", name, m.time)))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut lines = vec![
+            format!("scramble: {}", notation(&self.scramble, layers)?),
+            format!("solve: {}", solve.join(" ")),
+            format!("time: {:.2}s, {} moves, {:.2} TPS", self.duration(), self.moves.len(), self.tps()),
+        ];
+        for split in self.splits(layers, method) {
+            lines.push(format!("{}: {:.2}s, {} moves", split.name, split.time, split.moves));
+        }
+        Ok(lines.join("\n"))
+    }
+
+    pub fn import(text: &str, layers: u32) -> Option<Self> {
+        let mut scramble = None;
+        let mut moves = None;
+
+        for line in text.lines().map(str::trim) {
+            if let Some(s) = line.strip_prefix("scramble:") {
+                scramble = Some(parse_notation(s, layers)?);
+            } else if let Some(s) = line.strip_prefix("solve:") {
+                let timed = s.split_whitespace().map(|m| {
+                    let (movement, time) = m.split_once('@')?;
+                    Some(TimedMovement { movement: parse_movement(movement, layers)?, time: time.parse().ok()? })
+                });
+                moves = Some(timed.collect::<Option<Vec<_>>>()?);
+            }
+        }
+
+        Some(Reconstruction { scramble: scramble?, moves: moves? })
+    }
+}
+
+/// Records the movements the user makes, from a scramble until the cube is solved.
+#[derive(Resource, Clone, Default, Serialize, Deserialize)]
+pub struct Recorder {
+    /// Solve being recorded, or the last one.
+    pub reconstruction: Reconstruction,
+    pub recording: bool,
+    /// Solves of the session, oldest first.
+    pub solves: Vec<Reconstruction>,
+    /// Elapsed time of the app at the first movement.
+    #[serde(skip)]
+    start: Option<f64>,
+}
+
+impl Recorder {
+    /// Starts recording a solve of the scramble. A scramble of an unsolved cube can't be reconstructed,
+    /// nothing is recorded then.
+    pub fn scramble(&mut self, puzzle: &PuzzleState, scramble: &[Movement]) {
+        self.reconstruction = Reconstruction { scramble: scramble.to_vec(), moves: vec![] };
+        self.recording = puzzle.is_solved();
+        self.start = None;
+    }
+
+    /// Records a movement made at `now`, the elapsed time of the app in seconds.
+    /// A solve restored from a session resumes from the time of its last movement.
+    pub fn record(&mut self, movement: Movement, now: f64) {
+        if !self.recording {
+            return;
+        }
+        let start = *self.start.get_or_insert(now - self.reconstruction.duration() as f64);
+        self.reconstruction.moves.push(TimedMovement { movement, time: (now - start) as f32 });
+    }
+
+    /// Fastest time of the session.
+    pub fn best(&self) -> Option<f32> {
+        self.solves.iter().map(Reconstruction::duration).reduce(f32::min)
+    }
+
+    /// Mean time of the session.
+    pub fn mean(&self) -> Option<f32> {
+        if self.solves.is_empty() {
+            return None;
+        }
+        Some(self.solves.iter().map(Reconstruction::duration).sum::<f32>() / self.solves.len() as f32)
+    }
+}
+
+/// Stops recording once the cube is solved. The solve is kept only if it solves the scramble,
+/// movements made by the solver or the tutorials are not recorded.
+pub fn recorder_handler(
+    mut recorder: ResMut<Recorder>,
+    status: Res<ActionStatus>,
+    puzzle: Res<Puzzle>,
+) {
+    let idle = status.action_queue.is_empty() && status.cur_action.is_none();
+    if !recorder.recording || recorder.reconstruction.moves.is_empty() || !idle || !puzzle.is_solved() {
+        return;
+    }
+
+    recorder.recording = false;
+    let mut replay = PuzzleState::new(puzzle.layers());
+    replay.apply_all(&recorder.reconstruction.scramble);
+    replay.apply_all(&recorder.reconstruction.movements());
+    if replay.is_solved() {
+        let solve = recorder.reconstruction.clone();
+        recorder.solves.push(solve);
+    } else {
+        recorder.reconstruction.moves.clear();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn timed(moves: &str, times: &[f32]) -> Vec<TimedMovement> {
+        let movements = parse_notation(moves, 3).unwrap();
+        movements.into_iter().zip(times).map(|(movement, &time)| TimedMovement { movement, time }).collect()
+    }
+
+    #[test]
+    fn test_export_import() {
+        let reconstruction = Reconstruction {
+            scramble: parse_notation("R U R' F2 D", 3).unwrap(),
+            moves: timed("D' y F2 x' Rw U' R' U'", &[0.0, 0.25, 0.5, 0.75, 1.0, 1.25, 1.5, 2.0]),
+        };
+        let text = reconstruction.export(3, SolveMethod::Cfop).unwrap();
+        assert!(text.starts_with("scramble: R U R' F2 D\nsolve: D'@0.00 y@0.25 F2@0.50"));
+        assert!(text.contains("time: 2.00s, 8 moves, 4.00 TPS"));
+        assert_eq!(Reconstruction::import(&text, 3), Some(reconstruction));
+
+        assert_eq!(Reconstruction::import("scramble: R U\nsolve: R@0.0 U", 3), None);
+        assert_eq!(Reconstruction::import("solve: R@0.0", 3), None);
+    }
+
+    #[test]
+    fn test_splits() {
+        let t_perm = "R U R' U' R' F R2 U' R' U' R U R' F'";
+        let reconstruction = Reconstruction {
+            scramble: parse_notation(&format!("{t_perm} F R U R' U' F' D"), 3).unwrap(),
+            moves: timed(&format!("D' F U R U' R' F' {t_perm}"), &(0..21).map(|i| i as f32 * 0.5).collect::<Vec<_>>()),
+        };
+
+        let splits = reconstruction.splits(3, SolveMethod::Cfop);
+        assert_eq!(splits, [
+            Split { name: "Cross", moves: 1, time: 0.0 },
+            Split { name: "F2L", moves: 0, time: 0.0 },
+            Split { name: "OLL", moves: 6, time: 3.0 },
+            Split { name: "PLL", moves: 14, time: 7.0 },
+        ]);
+        assert_eq!(reconstruction.tps(), 2.1);
+    }
+}
@@ -0,0 +1,151 @@
+use std::{fmt, io};
+use serde::{Deserialize, Serialize};
+use crate::{puzzle::{Movement, PuzzleState}, recorder::Recorder, settings::Settings, storage};
+
+/// Key the session is saved under.
+const SESSION_KEY: &str = "session";
+/// Version of the saved format, increased whenever it changes.
+pub const SESSION_VERSION: u32 = 1;
+
+/// Everything needed to continue where the user left off.
+#[derive(Serialize, Deserialize)]
+pub struct Session {
+    pub version: u32,
+    pub puzzle: PuzzleState,
+    pub history: Vec<Movement>,
+    pub settings: Settings,
+    pub recorder: Recorder,
+}
+
+#[derive(Debug)]
+pub enum SessionError {
+    Storage(io::Error),
+    Format(serde_json::Error),
+    /// Saved with another version of the format.
+    Version(u32),
+    /// Saved for a cube with another number of layers, or with invalid facelets.
+    Puzzle(u32),
+    /// A movement outside the cube or an invalid time in the history or the recorded solves.
+    Movements,
+}
+
+impl fmt::Display for SessionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SessionError::Storage(e) => write!(f, "storage error: {e}"),
+            SessionError::Format(e) => write!(f, "invalid session: {e}"),
+            SessionError::Version(v) => write!(f, "session saved with version {v}, expected {SESSION_VERSION}"),
+            SessionError::Puzzle(layers) => write!(f, "session saved for a {layers}x{layers} cube"),
+            SessionError::Movements => write!(f, "invalid movements in the session"),
+        }
+    }
+}
+
+impl Session {
+    pub fn save(&self) -> Result<(), SessionError> {
+        storage::save(SESSION_KEY, &self.to_json()?).map_err(SessionError::Storage)
+    }
+
+    /// Loads the saved session of a cube of `layers` layers.
+    pub fn load(layers: u32) -> Result<Self, SessionError> {
+        let json = storage::load(SESSION_KEY).map_err(SessionError::Storage)?;
+        Self::from_json(&json, layers)
+    }
+
+    fn to_json(&self) -> Result<String, SessionError> {
+        serde_json::to_string_pretty(self).map_err(SessionError::Format)
+    }
+
+    fn from_json(json: &str, layers: u32) -> Result<Self, SessionError> {
+        // the version is read on its own, sessions of other versions may not parse
+        #[derive(Deserialize)]
+        struct Version {
+            version: u32,
+        }
+
+        let Version { version } = serde_json::from_str(json).map_err(SessionError::Format)?;
+        if version != SESSION_VERSION {
+            return Err(SessionError::Version(version));
+        }
+
+        let mut session: Session = serde_json::from_str(json).map_err(SessionError::Format)?;
+        if session.puzzle.layers() != layers || !session.puzzle.is_valid() {
+            return Err(SessionError::Puzzle(session.puzzle.layers()));
+        }
+        let recorder = &session.recorder;
+        let valid = session.history.iter().all(|m| m.layers.fits(layers))
+            && recorder.reconstruction.is_valid(layers)
+            && recorder.solves.iter().all(|solve| solve.is_valid(layers));
+        if !valid {
+            return Err(SessionError::Movements);
+        }
+        // the settings are checked like the saved settings, see `Settings::load`
+        session.settings = session.settings.checked();
+        Ok(session)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{notation::parse_notation, puzzle::LayerRange, recorder::{Reconstruction, TimedMovement}};
+
+    fn session() -> Session {
+        let history = parse_notation("R U R' U' x", 3).unwrap();
+        let mut puzzle = PuzzleState::new(3);
+        puzzle.apply_all(&history);
+
+        Session {
+            version: SESSION_VERSION,
+            puzzle,
+            history,
+            settings: Settings::default(),
+            recorder: Recorder::default(),
+        }
+    }
+
+    #[test]
+    fn test_json() {
+        let json = session().to_json().unwrap();
+        let loaded = Session::from_json(&json, 3).unwrap();
+        assert_eq!(loaded.puzzle, session().puzzle);
+        assert_eq!(loaded.history, session().history);
+
+        assert!(matches!(Session::from_json(&json, 4), Err(SessionError::Puzzle(3))));
+        assert!(matches!(Session::from_json("{}", 3), Err(SessionError::Format(_))));
+
+        let newer = json.replacen(&format!("\"version\": {SESSION_VERSION}"), "\"version\": 99", 1);
+        assert!(matches!(Session::from_json(&newer, 3), Err(SessionError::Version(99))));
+    }
+
+    #[test]
+    fn test_invalid_movements() {
+        let mut saved = session();
+        saved.history.push(Movement { layers: LayerRange::single(3), ..saved.history[0] });
+        assert!(matches!(Session::from_json(&saved.to_json().unwrap(), 3), Err(SessionError::Movements)));
+
+        let mut saved = session();
+        let movement = saved.history[0];
+        saved.recorder.reconstruction.moves.push(TimedMovement { movement, time: -1.0 });
+        assert!(matches!(Session::from_json(&saved.to_json().unwrap(), 3), Err(SessionError::Movements)));
+
+        let mut saved = session();
+        let solve = Reconstruction {
+            scramble: vec![Movement { layers: LayerRange { start: 2, end: 1 }, ..movement }],
+            moves: vec![TimedMovement { movement, time: 0.5 }],
+        };
+        saved.recorder.solves.push(solve);
+        assert!(matches!(Session::from_json(&saved.to_json().unwrap(), 3), Err(SessionError::Movements)));
+    }
+
+    #[test]
+    fn test_checked_settings() {
+        let mut saved = session();
+        saved.settings.layer_rotation_speed = -1.0;
+        saved.settings.color_up = saved.settings.color_down;
+
+        let loaded = Session::from_json(&saved.to_json().unwrap(), 3).unwrap();
+        assert_eq!(loaded.settings.layer_rotation_speed, Settings::default().layer_rotation_speed);
+        assert!(loaded.settings.has_distinct_colors());
+    }
+}
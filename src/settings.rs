@@ -1,7 +1,21 @@
+use std::{io, ops::RangeInclusive};
+
 use bevy::prelude::*;
 use bevy::color::palettes::css;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use crate::{puzzle::Side, storage};
+
+/// Key the settings are saved under.
+const SETTINGS_KEY: &str = "settings";
+
+/// Ranges of the settings, shared by the sliders and the checks of the loaded settings.
+pub const LAYERS: RangeInclusive<u32> = 2..=10;
+pub const VIEW_ROTATION_SPEEDS: RangeInclusive<f32> = 1.0..=10.0;
+pub const LAYER_ROTATION_SPEEDS: RangeInclusive<f32> = 1.0..=50.0;
 
-#[derive(Resource)]
+#[derive(Resource, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
 pub struct Settings {
     pub layers: u32,
     pub color_up: Color,
@@ -15,6 +29,322 @@ pub struct Settings {
     pub view_rotation_speed: f32,
     pub layer_rotation_speed: f32,
     pub rotation_trigger_value: f32,
+    pub easing: Easing,
+    /// Animate consecutive moves on parallel layers of the same axis at once.
+    pub overlap_parallel_moves: bool,
+
+    pub training_mode: TrainingMode,
+    /// Method the recorded solves are split by.
+    pub solve_method: SolveMethod,
+    /// Show the unfolded net of the cube.
+    pub show_net: bool,
+    pub key_bindings: KeyBindings,
+}
+
+/// Keys rotating the whole cube, shift gives the inverse rotation.
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+pub struct KeyBindings {
+    pub rotate_x: KeyCode,
+    pub rotate_y: KeyCode,
+    pub rotate_z: KeyCode,
+}
+
+impl Default for KeyBindings {
+    fn default() -> Self {
+        KeyBindings {
+            rotate_x: KeyCode::KeyX,
+            rotate_y: KeyCode::KeyY,
+            rotate_z: KeyCode::KeyZ,
+        }
+    }
+}
+
+/// Colour scheme presets.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum ColorScheme {
+    Western,
+    Japanese,
+    HighContrast,
+    ColorBlindSafe,
+}
+
+impl ColorScheme {
+    pub const ALL: [ColorScheme; 4] = [
+        ColorScheme::Western,
+        ColorScheme::Japanese,
+        ColorScheme::HighContrast,
+        ColorScheme::ColorBlindSafe,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            ColorScheme::Western => "western",
+            ColorScheme::Japanese => "japanese",
+            ColorScheme::HighContrast => "high contrast",
+            ColorScheme::ColorBlindSafe => "colour-blind safe",
+        }
+    }
+
+    /// Colours of the up, down, left, right, front and back faces.
+    pub fn colors(&self) -> [Color; 6] {
+        match self {
+            ColorScheme::Western => [
+                Color::Srgba(css::YELLOW), Color::Srgba(css::WHITE), Color::Srgba(css::RED),
+                Color::Srgba(css::ORANGE), Color::Srgba(css::GREEN), Color::Srgba(css::BLUE),
+            ],
+            // blue opposite white, green opposite yellow
+            ColorScheme::Japanese => [
+                Color::Srgba(css::WHITE), Color::Srgba(css::BLUE), Color::Srgba(css::GREEN),
+                Color::Srgba(css::YELLOW), Color::Srgba(css::RED), Color::Srgba(css::ORANGE),
+            ],
+            ColorScheme::HighContrast => [
+                Color::srgb(1.0, 1.0, 0.0), Color::srgb(1.0, 1.0, 1.0), Color::srgb(1.0, 0.0, 0.0),
+                Color::srgb(1.0, 0.0, 1.0), Color::srgb(0.0, 1.0, 0.0), Color::srgb(0.0, 0.3, 1.0),
+            ],
+            // Okabe-Ito palette
+            ColorScheme::ColorBlindSafe => [
+                Color::srgb_u8(0xf0, 0xe4, 0x42), Color::srgb_u8(0xff, 0xff, 0xff), Color::srgb_u8(0xd5, 0x5e, 0x00),
+                Color::srgb_u8(0x56, 0xb4, 0xe9), Color::srgb_u8(0x00, 0x9e, 0x73), Color::srgb_u8(0x00, 0x72, 0xb2),
+            ],
+        }
+    }
+}
+
+/// Animation curve of a layer rotation.
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+pub enum Easing {
+    Linear,
+    EaseInOut,
+    /// Turns slightly too far, then settles back.
+    Overshoot,
+    Instant,
+}
+
+impl Easing {
+    pub const ALL: [Easing; 4] = [
+        Easing::Linear,
+        Easing::EaseInOut,
+        Easing::Overshoot,
+        Easing::Instant,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Easing::Linear => "linear",
+            Easing::EaseInOut => "ease in out",
+            Easing::Overshoot => "overshoot",
+            Easing::Instant => "instant",
+        }
+    }
+
+    /// Maps the progress of a rotation, from 0 to 1, to the fraction of the angle already turned.
+    pub fn apply(&self, t: f32) -> f32 {
+        match self {
+            Easing::Linear => t,
+            Easing::EaseInOut => {
+                if t < 0.5 {
+                    4.0 * t * t * t
+                } else {
+                    1.0 - (2.0 - 2.0 * t).powi(3) / 2.0
+                }
+            },
+            Easing::Overshoot => {
+                // ease out back
+                let c1 = 1.70158;
+                let c3 = c1 + 1.0;
+                1.0 + c3 * (t - 1.0).powi(3) + c1 * (t - 1.0).powi(2)
+            },
+            Easing::Instant => if t < 1.0 { 0.0 } else { 1.0 },
+        }
+    }
+}
+
+/// Which subset of the cube the training scramble leaves unsolved.
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+pub enum TrainingMode {
+    CrossSolved,
+    F2LSolved,
+    OLLCrossSolved,
+    OLLSolved,
+    CornersSolved,
+    EdgesSolved,
+}
+
+impl TrainingMode {
+    pub const ALL: [TrainingMode; 6] = [
+        TrainingMode::CrossSolved,
+        TrainingMode::F2LSolved,
+        TrainingMode::OLLCrossSolved,
+        TrainingMode::OLLSolved,
+        TrainingMode::CornersSolved,
+        TrainingMode::EdgesSolved,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            TrainingMode::CrossSolved => "cross solved",
+            TrainingMode::F2LSolved => "F2L solved",
+            TrainingMode::OLLCrossSolved => "OLL cross solved",
+            TrainingMode::OLLSolved => "OLL solved",
+            TrainingMode::CornersSolved => "corners solved",
+            TrainingMode::EdgesSolved => "edges solved",
+        }
+    }
+}
+
+/// Method of a solve, giving the phases it is split into.
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+pub enum SolveMethod {
+    Beginner,
+    Cfop,
+    Roux,
+}
+
+impl SolveMethod {
+    pub const ALL: [SolveMethod; 3] = [SolveMethod::Beginner, SolveMethod::Cfop, SolveMethod::Roux];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            SolveMethod::Beginner => "beginner",
+            SolveMethod::Cfop => "CFOP",
+            SolveMethod::Roux => "Roux",
+        }
+    }
+}
+
+impl Settings {
+    /// Colours of the up, down, left, right, front and back faces, then of the bevels.
+    pub fn scheme(&self) -> [Color; 7] {
+        [
+            self.color_up, self.color_down, self.color_left,
+            self.color_right, self.color_front, self.color_back,
+            self.color_beleved,
+        ]
+    }
+
+    pub fn set_scheme(&mut self, scheme: ColorScheme) {
+        let [up, down, left, right, front, back] = scheme.colors();
+        self.color_up = up;
+        self.color_down = down;
+        self.color_left = left;
+        self.color_right = right;
+        self.color_front = front;
+        self.color_back = back;
+    }
+
+    pub fn side_color(&self, side: Side) -> Color {
+        match side {
+            Side::U => self.color_up,
+            Side::D => self.color_down,
+            Side::L => self.color_left,
+            Side::R => self.color_right,
+            Side::F => self.color_front,
+            Side::B => self.color_back,
+        }
+    }
+
+    /// Meshes are recoloured by replacing each colour by its counterpart, so the colours must all be different.
+    pub fn has_distinct_colors(&self) -> bool {
+        let scheme = self.scheme();
+        scheme.iter().enumerate().all(|(i, a)| scheme[i + 1..].iter().all(|b| a != b))
+    }
+
+    /// Loads the saved settings, the defaults are used when nothing is saved.
+    pub fn load() -> Settings {
+        match storage::load(SETTINGS_KEY) {
+            Ok(json) => Settings::from_json(&json),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Settings::default(),
+            Err(e) => {
+                warn!("can't load the settings, using the defaults: {e}");
+                Settings::default()
+            },
+        }
+    }
+
+    pub fn save(&self) {
+        let saved = serde_json::to_string_pretty(self).map_err(io::Error::from)
+            .and_then(|json| storage::save(SETTINGS_KEY, &json));
+        if let Err(e) = saved {
+            warn!("can't save the settings: {e}");
+        }
+    }
+
+    /// Reads the settings, invalid values fall back to their defaults with a warning.
+    fn from_json(json: &str) -> Settings {
+        let loaded: serde_json::Map<String, Value> = match serde_json::from_str(json) {
+            Ok(loaded) => loaded,
+            Err(e) => {
+                warn!("invalid settings, using the defaults: {e}");
+                return Settings::default();
+            },
+        };
+
+        // every value is read on its own, so an invalid one keeps the others
+        let mut settings = Settings::default();
+        for (key, value) in loaded {
+            let Ok(Value::Object(mut fields)) = serde_json::to_value(&settings) else {
+                unreachable!()
+            };
+            fields.insert(key.clone(), value);
+            match serde_json::from_value(Value::Object(fields)) {
+                Ok(read) => settings = read,
+                Err(e) => warn!("invalid setting {key}, using the default: {e}"),
+            }
+        }
+
+        settings.checked()
+    }
+
+    /// Replaces the values out of their range by the defaults, with a warning.
+    pub(crate) fn checked(mut self) -> Settings {
+        let default = Settings::default();
+
+        if !LAYERS.contains(&self.layers) {
+            warn!("invalid setting layers {}, using the default", self.layers);
+            self.layers = default.layers;
+        }
+        if !VIEW_ROTATION_SPEEDS.contains(&self.view_rotation_speed) {
+            warn!("invalid setting view_rotation_speed {}, using the default", self.view_rotation_speed);
+            self.view_rotation_speed = default.view_rotation_speed;
+        }
+        if !LAYER_ROTATION_SPEEDS.contains(&self.layer_rotation_speed) {
+            warn!("invalid setting layer_rotation_speed {}, using the default", self.layer_rotation_speed);
+            self.layer_rotation_speed = default.layer_rotation_speed;
+        }
+        if !(self.rotation_trigger_value.is_finite() && self.rotation_trigger_value > 0.0) {
+            warn!("invalid setting rotation_trigger_value {}, using the default", self.rotation_trigger_value);
+            self.rotation_trigger_value = default.rotation_trigger_value;
+        }
+        if !self.has_distinct_colors() {
+            warn!("the colours are not all different, using the default colours");
+            self.set_scheme(ColorScheme::Western);
+            self.color_beleved = default.color_beleved;
+        }
+        let keys = self.key_bindings;
+        if keys.rotate_x == keys.rotate_y || keys.rotate_y == keys.rotate_z || keys.rotate_z == keys.rotate_x {
+            warn!("a key is bound twice, using the default key bindings");
+            self.key_bindings = default.key_bindings;
+        }
+
+        self
+    }
+}
+
+/// Shortest time between two saves in seconds, a dragged slider changes the settings every frame.
+const SAVE_INTERVAL: f64 = 1.0;
+
+/// Saves the settings whenever they change, at most once every `SAVE_INTERVAL`, the last change being saved once
+/// the interval has passed. The settings loaded at startup are not saved again.
+/// Colours being edited into a duplicate are saved once they are all different again.
+pub fn save_settings(settings: Res<Settings>, time: Res<Time>, mut saved: Local<Option<(Settings, f64)>>) {
+    let now = time.elapsed_seconds_f64();
+    let (saved, saved_at) = saved.get_or_insert_with(|| (settings.clone(), now));
+    if *saved == *settings || !settings.has_distinct_colors() || now - *saved_at < SAVE_INTERVAL {
+        return;
+    }
+    settings.save();
+    *saved = settings.clone();
+    *saved_at = now;
 }
 
 impl Default for Settings {
@@ -32,6 +362,42 @@ impl Default for Settings {
             view_rotation_speed: 5.0,
             layer_rotation_speed: 5.0,
             rotation_trigger_value: 0.8,
+            easing: Easing::Linear,
+            overlap_parallel_moves: false,
+
+            training_mode: TrainingMode::F2LSolved,
+            solve_method: SolveMethod::Cfop,
+            show_net: false,
+            key_bindings: KeyBindings::default(),
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_from_json() {
+        let mut settings = Settings { layers: 4, layer_rotation_speed: 20.0, ..Settings::default() };
+        settings.set_scheme(ColorScheme::Japanese);
+        let json = serde_json::to_string(&settings).unwrap();
+        assert!(Settings::from_json(&json) == settings);
+
+        // invalid values keep the others
+        let loaded = Settings::from_json(r#"{"layers": 40, "view_rotation_speed": "fast", "layer_rotation_speed": 20.0}"#);
+        assert_eq!(loaded.layers, 3);
+        assert_eq!(loaded.view_rotation_speed, 5.0);
+        assert_eq!(loaded.layer_rotation_speed, 20.0);
+
+        let mut duplicated = settings.clone();
+        duplicated.color_up = duplicated.color_down;
+        duplicated.key_bindings.rotate_x = KeyCode::KeyY;
+        let loaded = Settings::from_json(&serde_json::to_string(&duplicated).unwrap());
+        assert!(loaded.scheme() == Settings::default().scheme());
+        assert_eq!(loaded.key_bindings, KeyBindings::default());
+        assert_eq!(loaded.layer_rotation_speed, 20.0);
+
+        assert!(Settings::from_json("not json") == Settings::default());
+    }
+}
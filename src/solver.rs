@@ -1,6 +1,6 @@
-use bevy::color::Color;
-use kewb::{CubieCube, DataTable, FaceCube, Move, Solver};
-use crate::cube::{Cube, Direction, Face, Movement, RotateAxis};
+use bevy::utils::{Duration, Instant};
+use kewb::{beginner, cfop, roux, generators::*, last_layer::*, scramble::scramble_from_state, CubieCube, DataTable, FaceCube, Move, Solution, Solver, StagedSolution};
+use crate::{puzzle::{Movement, PuzzleState, Side}, settings::{SolveMethod, TrainingMode}};
 //              |************|
 //              |*U1**U2**U3*|
 //              |************|
@@ -27,202 +27,299 @@ use crate::cube::{Cube, Direction, Face, Movement, RotateAxis};
 // R3, R4, R5, R6, R7, R8, R9, F1, F2, F3, F4, F5, F6, F7, F8, F9, D1, D2, D3, D4, D5, D6, D7, D8, D9, L1, L2, L3, L4,
 // L5, L6, L7, L8, L9, B1, B2, B3, B4, B5, B6, B7, B8, B9 of the enum constants.
 
-struct CubeState {
-    color_up: Color,
-    color_down: Color,
-    color_front: Color,
-    color_back: Color,
-    color_left: Color,
-    color_right: Color,
-}
-
-impl From<&Vec<Cube>> for CubeState {
-    fn from(value: &Vec<Cube>) -> Self {
-        let [mut f, mut b, mut u, mut d, mut l, mut r] = [Color::BLACK; 6];
-        for cube in value {
-            let [x, y, z] = cube.coord;
-            if x == 2 && y == 1 && z == 1 {
-                r = *cube.colors.get(&Face::RIGHT).unwrap();
-            } else if x == 0 && y == 1 && z == 1 {
-                l = *cube.colors.get(&Face::LEFT).unwrap();
-            } else if y == 2 && x == 1 && z == 1 {
-                u = *cube.colors.get(&Face::UP).unwrap();
-            } else if y == 0 && x == 1 && z == 1 {
-                d = *cube.colors.get(&Face::DOWN).unwrap();
-            } else if z == 2 && x == 1 && y == 1 {
-                f = *cube.colors.get(&Face::FRONT).unwrap();
-            } else if z == 0 && x == 1 && y == 1 {
-                b = *cube.colors.get(&Face::BACK).unwrap();
-            }
-        }
+/// Facelets of the puzzle as a cube definition string, each facelet named after the face whose center has its colour.
+/// Only 3x3 cubes have a cube definition string.
+fn cube_state_to_string(puzzle: &PuzzleState) -> Option<String> {
+    if puzzle.layers() != 3 {
+        return None;
+    }
+    let facelets = puzzle.facelets();
+    let centers: Vec<Side> = facelets.chunks(9).map(|side| side[4]).collect();
+    facelets.iter().map(|s| Some(Side::ALL[centers.iter().position(|c| c == s)?].letter())).collect()
+}
 
-        Self { color_up: u, color_down: d, color_front: f, color_back: b, color_left: l, color_right: r }
+pub fn generate_moves_from_string(moves: Vec<Move>) -> Vec<Movement> {
+    moves.into_iter().map(Movement::from).collect()
+}
+
+/// State of the puzzle for the kewb solvers, `None` if the puzzle is not a 3x3 cube.
+fn cube_state(puzzle: &PuzzleState) -> Option<CubieCube> {
+    let state_str = cube_state_to_string(puzzle)?;
+    CubieCube::try_from(&FaceCube::try_from(state_str.as_str()).ok()?).ok()
+}
+
+fn generate_training_state(mode: TrainingMode) -> CubieCube {
+    match mode {
+        TrainingMode::CrossSolved => generate_state_cross_solved(),
+        TrainingMode::F2LSolved => generate_state_f2l_solved(),
+        TrainingMode::OLLCrossSolved => generate_state_oll_cross_solved(),
+        TrainingMode::OLLSolved => generate_state_oll_solved(),
+        TrainingMode::CornersSolved => generate_state_corners_solved(),
+        TrainingMode::EdgesSolved => generate_state_edges_solved(),
     }
 }
 
-fn index_u(x: i32, z: i32) -> usize { (z * 3 + x) as usize }
-fn index_d(x: i32, z: i32) -> usize { ((-z + 2) * 3 + x) as usize }
-fn index_f(x: i32, y: i32) -> usize { ((-y + 2) * 3 + x) as usize }
-fn index_b(x: i32, y: i32) -> usize { ((-y + 2) * 3 + (-x + 2)) as usize }
-fn index_l(y: i32, z: i32) -> usize { ((-y + 2) * 3 + z) as usize }
-fn index_r(y: i32, z: i32) -> usize { ((-y + 2) * 3 + (-z + 2)) as usize }
-
-fn get_original_position(cube_state: &CubeState, color: &Color) -> char {
-    if cube_state.color_up == *color {
-        'U'
-    } else if cube_state.color_down == *color {
-        'D'
-    } else if cube_state.color_front == *color {
-        'F'
-    } else if cube_state.color_back == *color {
-        'B'
-    } else if cube_state.color_left == *color {
-        'L'
-    } else if cube_state.color_right == *color {
-        'R'
-    } else {
-        unreachable!()
+/// Solves the cube with the two phase solver, also returning the time spent searching.
+/// Only 3x3 cubes are solved.
+pub async fn solve(
+    puzzle: PuzzleState,
+) -> Option<(Solution, Duration)> {
+    let state = cube_state(&puzzle)?;
+    let table = DataTable::default();
+    let mut solver = Solver::new(&table, 23);
+    let start = Instant::now();
+    let solution = solver.solve(state)?;
+    Some((solution, start.elapsed()))
+}
+
+/// Solves the cube with the layer by layer beginner method.
+pub async fn solve_beginner(
+    puzzle: PuzzleState,
+) -> Option<StagedSolution> {
+    beginner::solve(cube_state(&puzzle)?).ok()
+}
+
+/// Solves the cube with CFOP: optimal cross, pair by pair F2L, OLL and PLL.
+pub async fn solve_cfop(
+    puzzle: PuzzleState,
+) -> Option<StagedSolution> {
+    cfop::solve(cube_state(&puzzle)?).ok()
+}
+
+/// Solves the cube with Roux: first block, second block, last layer corners and the last six edges.
+pub async fn solve_roux(
+    puzzle: PuzzleState,
+) -> Option<StagedSolution> {
+    roux::solve(cube_state(&puzzle)?).ok()
+}
+
+/// Recognizes the OLL or PLL case of the cube, if its first two layers are solved.
+pub fn recognize_last_layer(puzzle: &PuzzleState) -> Option<CaseMatch> {
+    let state = cube_state(puzzle)?;
+    recognize_pll(&state).or_else(|| recognize_oll(&state))
+}
+
+/// Brings the cube back to the solved state, then scrambles it into a random state of the given training mode.
+/// Only 3x3 cubes are scrambled, there are no movements for other cubes.
+pub async fn training_scramble(
+    puzzle: PuzzleState,
+    mode: TrainingMode,
+) -> Vec<Movement> {
+    let Some(state) = cube_state(&puzzle) else {
+        return vec![];
+    };
+    let table = DataTable::default();
+    let mut solver = Solver::new(&table, 23);
+    let mut moves = solver.solve(state).unwrap().get_all_moves();
+    solver.clear();
+    moves.extend(scramble_from_state(generate_training_state(mode), &mut solver).unwrap());
+    generate_moves_from_string(moves)
+}
+
+/// Brings the cube back to the solved state, then applies the scramble of a reconstruction.
+/// Only 3x3 cubes are solved, other cubes must already be solved.
+pub async fn reconstruction_scramble(
+    puzzle: PuzzleState,
+    scramble: Vec<Movement>,
+) -> Vec<Movement> {
+    let mut moves = vec![];
+    if let Some(state) = cube_state(&puzzle).filter(|_| !puzzle.is_solved()) {
+        let table = DataTable::default();
+        let mut solver = Solver::new(&table, 23);
+        moves = generate_moves_from_string(solver.solve(state).unwrap().get_all_moves());
     }
+    moves.extend(scramble);
+    moves
 }
 
-fn cube_state_to_string(
-    cubes: &Vec<Cube>,
-    cube_state: &CubeState,
-) -> String {
-
-    let mut ret: Vec<char> = vec!['0'; 54];
-
-    for cube in cubes {
-        let [x, y, z] = cube.coord;
-        // U
-        if y == 2 {
-            let start: usize = 0;
-            ret[start + index_u(x, z)] = get_original_position(&cube_state, cube.colors.get(&Face::UP).unwrap());
-        } 
-        
-        // D
-        if y == 0 {
-            let start: usize = 27;
-            ret[start + index_d(x, z)] = get_original_position(&cube_state, cube.colors.get(&Face::DOWN).unwrap());
-        }
+/// Check of the state at the end of a phase, given the puzzle and its state for the kewb solvers.
+type PhaseCheck = fn(&PuzzleState, &CubieCube) -> bool;
 
-        // F
-        if z == 2 {
-            let start: usize = 18;
-            ret[start + index_f(x, y)] = get_original_position(&cube_state, cube.colors.get(&Face::FRONT).unwrap());
-        }
+/// Phases of CFOP and the check of the state at the end of each, every phase keeping the previous ones.
+const CFOP_PHASES: [(&str, PhaseCheck); 4] = [
+    ("Cross", |_, state| cfop::is_cross_solved(state)),
+    ("F2L", |_, state| is_f2l_solved(state)),
+    ("OLL", |_, state| is_f2l_solved(state) && is_ll_oriented(state)),
+    ("PLL", |_, state| *state == CubieCube::default()),
+];
 
-        // B
-        if z == 0 {
-            let start: usize = 45;
-            ret[start + index_b(x, y)] = get_original_position(&cube_state, cube.colors.get(&Face::BACK).unwrap());
-        }
+/// Phases of the beginner method, named as the stages of `kewb::beginner`.
+const BEGINNER_PHASES: [(&str, PhaseCheck); 6] = [
+    ("Cross", |_, state| cfop::is_cross_solved(state)),
+    ("First layer corners", |_, state| cfop::is_cross_solved(state) && (4..8).all(|i| state.cp[i] as usize == i && state.co[i] == 0)),
+    ("Second layer", |_, state| is_f2l_solved(state)),
+    ("Yellow cross", |_, state| is_f2l_solved(state) && state.eo[4..8].iter().all(|&eo| eo == 0)),
+    ("OLL", |_, state| is_f2l_solved(state) && is_ll_oriented(state)),
+    ("PLL", |_, state| *state == CubieCube::default()),
+];
 
-        // L
-        if x == 0 {
-            let start: usize = 36;
-            ret[start + index_l(y, z)] = get_original_position(&cube_state, cube.colors.get(&Face::LEFT).unwrap());
-        }
+/// Phases of Roux, named as the stages of `kewb::roux`. M moves turn the centers the kewb state is read from, so the
+/// blocks are read from the facelets, see `is_block_solved`.
+const ROUX_PHASES: [(&str, PhaseCheck); 4] = [
+    ("First block", |puzzle, _| is_block_solved(puzzle, &LEFT_BLOCK)),
+    ("Second block", |puzzle, _| are_blocks_solved(puzzle)),
+    (roux::LL_CORNERS_STAGE, |puzzle, _| are_blocks_solved(puzzle) && are_ll_corners_solved(puzzle)),
+    ("LSE", |puzzle, _| puzzle.is_solved()),
+];
 
-        // R
-        if x == 2 {
-            let start: usize = 9;
-            ret[start + index_r(y, z)] = get_original_position(&cube_state, cube.colors.get(&Face::RIGHT).unwrap());
-        }
+/// Facelets of a 1x2x3 block of Roux, numbered from 0 in each side as in the diagram above: the bottom two rows of
+/// the side of the block, then its columns on the D, F and B sides.
+struct Block {
+    side: Side,
+    down: [usize; 3],
+    front: [usize; 2],
+    back: [usize; 2],
+}
+
+const LEFT_BLOCK: Block = Block { side: Side::L, down: [0, 3, 6], front: [3, 6], back: [5, 8] };
+const RIGHT_BLOCK: Block = Block { side: Side::R, down: [2, 5, 8], front: [5, 8], back: [3, 6] };
+
+fn facelet(puzzle: &PuzzleState, side: Side, index: usize) -> Side {
+    puzzle.facelets()[side as usize * 9 + index]
+}
+
+/// Colours of the D, F and B columns of a solved block: the bottom two rows of its side have the colour of its center
+/// and each column has a single colour, which after M moves is not the one of the center of the side.
+fn block_colors(puzzle: &PuzzleState, block: &Block) -> Option<[Side; 3]> {
+    let column = |side: Side, facelets: &[usize]| {
+        let color = facelet(puzzle, side, facelets[0]);
+        facelets.iter().all(|&i| facelet(puzzle, side, i) == color).then_some(color)
+    };
+    let center = facelet(puzzle, block.side, 4);
+    if (3..9).any(|i| facelet(puzzle, block.side, i) != center) {
+        return None;
     }
+    Some([column(Side::D, &block.down)?, column(Side::F, &block.front)?, column(Side::B, &block.back)?])
+}
 
-    ret.iter().collect()
+fn is_block_solved(puzzle: &PuzzleState, block: &Block) -> bool {
+    block_colors(puzzle, block).is_some()
 }
 
-fn generate_moves_from_string(moves: Vec<Move>) -> Vec<Movement> {
-    let mut ret = Vec::new();
-    for step in moves {
-        let mut m = Movement {
-            axis: RotateAxis::X,
-            layer: 0,
-            direction: Direction::Clockwise
-        };
+/// Both blocks are solved with the same D, F and B colours.
+fn are_blocks_solved(puzzle: &PuzzleState) -> bool {
+    match (block_colors(puzzle, &LEFT_BLOCK), block_colors(puzzle, &RIGHT_BLOCK)) {
+        (Some(left), Some(right)) => left == right,
+        _ => false,
+    }
+}
 
-        let mut double = false;
-
-        match step {
-            Move::U | Move::U2 | Move::U3 => {
-                m.axis = RotateAxis::Y;
-                m.layer = 2;
-                if step == Move::U2 {
-                    double = true;
-                }
-                if step == Move::U3 {
-                    m.direction = Direction::CounterClockwise;
-                }
-            },
-            Move::D | Move::D2 | Move::D3 => {
-                m.axis = RotateAxis::Y;
-                m.layer = 0;
-                if step == Move::D2 {
-                    double = true;
-                }
-                if step == Move::D {
-                    m.direction = Direction::CounterClockwise;
-                }
-            },
-            Move::R | Move::R2 | Move::R3 => {
-                m.axis = RotateAxis::X;
-                m.layer = 2;
-                if step == Move::R2 {
-                    double = true;
-                }
-                if step == Move::R3 {
-                    m.direction = Direction::CounterClockwise;
-                }
-            },
-            Move::L | Move::L2 | Move::L3 => {
-                m.axis = RotateAxis::X;
-                m.layer = 0;
-                if step == Move::L2 {
-                    double = true;
-                }
-                if step == Move::L {
-                    m.direction = Direction::CounterClockwise;
-                }
-            },
-            Move::F | Move::F2 | Move::F3 => {
-                m.axis = RotateAxis::Z;
-                m.layer = 2;
-                if step == Move::F2 {
-                    double = true;
-                }
-                if step == Move::F3 {
-                    m.direction = Direction::CounterClockwise;
-                }
-            },
-            Move::B | Move::B2 | Move::B3 => {
-                m.axis = RotateAxis::Z;
-                m.layer = 0;
-                if step == Move::B2 {
-                    double = true;
-                }
-                if step == Move::B {
-                    m.direction = Direction::CounterClockwise;
-                }
-            },
+/// The U layer corners are solved up to a U turn: their top facelets have the same colour and each side has
+/// matching corner facelets.
+fn are_ll_corners_solved(puzzle: &PuzzleState) -> bool {
+    let top = [0, 2, 6, 8].map(|i| facelet(puzzle, Side::U, i));
+    top.iter().all(|&c| c == top[0])
+        && [Side::R, Side::F, Side::L, Side::B].iter().all(|&side| facelet(puzzle, side, 0) == facelet(puzzle, side, 2))
+}
+
+/// Splits a solve of the 3x3 from `start` into the phases of `method`, returning each phase reached and the number of
+/// movements made when it was completed. The phases are read from the states in the current orientation: the cross
+/// is on the D face and the Roux blocks on the L and R faces. Skipped phases end with the previous one.
+pub fn detect_phases(method: SolveMethod, start: &PuzzleState, movements: &[Movement]) -> Vec<(&'static str, usize)> {
+    let checks: &[(&str, PhaseCheck)] = match method {
+        SolveMethod::Beginner => &BEGINNER_PHASES,
+        SolveMethod::Cfop => &CFOP_PHASES,
+        SolveMethod::Roux => &ROUX_PHASES,
+    };
+    let mut phases = vec![];
+    let mut puzzle = start.clone();
+    for i in 0..=movements.len() {
+        if i > 0 {
+            puzzle.apply(&movements[i - 1]);
         }
-        
-        ret.push(m);
-        if double {
-            ret.push(m);
+        let Some(state) = cube_state(&puzzle) else {
+            break;
+        };
+        while let Some((name, is_done)) = checks.get(phases.len()) {
+            if !is_done(&puzzle, &state) {
+                break;
+            }
+            phases.push((*name, i));
         }
     }
-    ret
+
+    phases
 }
 
-pub async fn solve(
-    cubes: Vec<Cube>,
-) -> Vec<Movement> {
-    let cube_state = CubeState::from(&cubes);
-    let state_str = cube_state_to_string(&cubes, &cube_state);
-    let state = CubieCube::try_from(&FaceCube::try_from(state_str.as_str()).unwrap()).unwrap();
-    let table = DataTable::default();
-    let mut solver = Solver::new(&table, 23);
-    generate_moves_from_string(solver.solve(state).unwrap().get_all_moves())
-}
\ No newline at end of file
+#[cfg(test)]
+mod test {
+    use super::*;
+    use kewb::scramble::scramble_from_str;
+    use crate::puzzle::RotateAxis;
+
+    fn apply_scramble(puzzle: &mut PuzzleState, scramble: &str) -> Vec<Move> {
+        let moves = scramble_from_str(scramble).unwrap();
+        puzzle.apply_all(&generate_moves_from_string(moves.clone()));
+        moves
+    }
+
+    #[test]
+    fn test_moves_match_kewb() {
+        for scramble in [
+            "U D R L F B",
+            "U' D' R' L' F' B'",
+            "U2 D2 R2 L2 F2 B2",
+            "R U F' D2 L B' R2 D U' F2 L' B2 D' R",
+        ] {
+            let mut puzzle = PuzzleState::new(3);
+            let moves = apply_scramble(&mut puzzle, scramble);
+            assert_eq!(cube_state(&puzzle), Some(CubieCube::default().apply_moves(&moves)), "{}", scramble);
+        }
+    }
+
+    #[test]
+    fn test_rotations_follow_centers() {
+        // the solver reads the faces from the centers, so a rotated cube is still solved
+        let mut puzzle = PuzzleState::new(3);
+        puzzle.apply(&Movement::rotation(RotateAxis::X, false, 3));
+        assert_eq!(cube_state(&puzzle), Some(CubieCube::default()));
+
+        // R L' then y is F B' seen from the new orientation
+        let mut puzzle = PuzzleState::new(3);
+        apply_scramble(&mut puzzle, "R L'");
+        puzzle.apply(&Movement::rotation(RotateAxis::Y, false, 3));
+        let moves = scramble_from_str("F B'").unwrap();
+        assert_eq!(cube_state(&puzzle), Some(CubieCube::default().apply_moves(&moves)));
+    }
+
+    #[test]
+    fn test_other_sizes() {
+        for layers in [2, 4, 5] {
+            let mut puzzle = PuzzleState::new(layers);
+            assert_eq!(cube_state(&puzzle), None);
+            assert!(recognize_last_layer(&puzzle).is_none());
+
+            puzzle.apply(&Movement::rotation(RotateAxis::X, false, layers));
+            assert_eq!(cube_state_to_string(&puzzle), None);
+        }
+    }
+
+    #[test]
+    fn test_cfop_phases() {
+        let t_perm = "R U R' U' R' F R2 U' R' U' R U R' F'";
+        let mut start = PuzzleState::new(3);
+        apply_scramble(&mut start, &format!("{t_perm} F R U R' U' F' D"));
+
+        let solve = format!("D' F U R U' R' F' {t_perm}");
+        let movements = generate_moves_from_string(scramble_from_str(&solve).unwrap());
+        let phases = detect_phases(SolveMethod::Cfop, &start, &movements);
+
+        assert_eq!(phases, [("Cross", 1), ("F2L", 1), ("OLL", 7), ("PLL", 21)]);
+        assert!(detect_phases(SolveMethod::Cfop, &PuzzleState::new(4), &movements).is_empty());
+
+        let phases = detect_phases(SolveMethod::Beginner, &start, &movements);
+        assert_eq!(phases, [
+            ("Cross", 1), ("First layer corners", 1), ("Second layer", 1), ("Yellow cross", 7), ("OLL", 7), ("PLL", 21),
+        ]);
+    }
+
+    #[test]
+    fn test_roux_phases() {
+        let mut start = PuzzleState::new(3);
+        start.apply_all(&generate_moves_from_string(vec![Move::M, Move::U2, Move::M3, Move::U3, Move::R3, Move::L3]));
+
+        let solve = vec![Move::L, Move::R, Move::U, Move::M, Move::U2, Move::M3];
+        let phases = detect_phases(SolveMethod::Roux, &start, &generate_moves_from_string(solve));
+        assert_eq!(phases, [("First block", 1), ("Second block", 2), (roux::LL_CORNERS_STAGE, 2), ("LSE", 6)]);
+    }
+}
@@ -0,0 +1,41 @@
+//! Text saved under a key: a JSON file in the working directory on native, `localStorage` in the browser.
+
+use std::io;
+
+#[cfg(not(target_arch = "wasm32"))]
+fn path(key: &str) -> std::path::PathBuf {
+    std::path::PathBuf::from(format!("{key}.json"))
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn save(key: &str, value: &str) -> io::Result<()> {
+    std::fs::write(path(key), value)
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn load(key: &str) -> io::Result<String> {
+    std::fs::read_to_string(path(key))
+}
+
+#[cfg(target_arch = "wasm32")]
+fn local_storage() -> io::Result<web_sys::Storage> {
+    web_sys::window()
+        .and_then(|window| window.local_storage().ok().flatten())
+        .ok_or_else(|| io::Error::other("local storage is not available"))
+}
+
+#[cfg(target_arch = "wasm32")]
+pub fn save(key: &str, value: &str) -> io::Result<()> {
+    local_storage()?
+        .set_item(key, value)
+        .map_err(|_| io::Error::other("local storage is full"))
+}
+
+#[cfg(target_arch = "wasm32")]
+pub fn load(key: &str) -> io::Result<String> {
+    local_storage()?
+        .get_item(key)
+        .ok()
+        .flatten()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, format!("nothing saved as {key}")))
+}
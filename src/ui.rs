@@ -1,34 +1,252 @@
 use bevy::prelude::*;
 use bevy_async_task::{AsyncTaskRunner, AsyncTaskStatus};
 use bevy_egui::{egui, EguiContexts};
-use crate::{actions::{gen_random_movements, ActionStatus}, cube::{Cube, CubeInfo, Movement}, settings::Settings};
+use crate::{actions::{gen_random_movements, ActionStatus}, camera::{OrbitCamera, View}, cube::{reset_cubes, Cube, Puzzle}, notation::{movement_notation, notation}, playback::Playback, puzzle::{Movement, PuzzleState, RotateAxis}, recorder::{Reconstruction, Recorder, Split}, session::{Session, SESSION_VERSION}, settings::{ColorScheme, Easing, Settings, SolveMethod, TrainingMode, LAYER_ROTATION_SPEEDS, VIEW_ROTATION_SPEEDS}};
 use crate::solver::*;
+use bevy::{color::{ColorToComponents, ColorToPacked}, utils::Duration};
+use kewb::{last_layer::CaseMatch, Solution, StagedSolution};
+
+/// Last two phase solution found by "solve".
+#[derive(Resource, Default)]
+pub struct SolveResult {
+    pub solution: Option<Solution>,
+    pub solve_time: Duration,
+}
+
+/// Last layer case recognized on the puzzle, with the state it was recognized on.
+#[derive(Resource, Default)]
+pub struct LastLayerCase(pub Option<(PuzzleState, Option<CaseMatch>)>);
+
+/// Staged solution (beginner method, CFOP or Roux reference), played one stage at a time.
+#[derive(Resource, Default)]
+pub struct Tutorial {
+    pub solution: Option<StagedSolution>,
+    pub next_stage: usize,
+    /// State the puzzle is left in by the stages played so far, the solution is dropped once the puzzle is in
+    /// another state.
+    pub state: Option<PuzzleState>,
+}
 
 pub fn update_ui(
     mut contexts: EguiContexts,
     mut settings: ResMut<Settings>,
     mut status: ResMut<ActionStatus>,
-    cube_info: Res<CubeInfo>,
-    cube_query: Query<&Cube>,
-    mut task_runner: AsyncTaskRunner<Vec<Movement>>
+    mut orbit: ResMut<OrbitCamera>,
+    puzzle: Res<Puzzle>,
+    mut tutorial: ResMut<Tutorial>,
+    mut playback: ResMut<Playback>,
+    mut solve_result: ResMut<SolveResult>,
+    mut recorder: ResMut<Recorder>,
+    time: Res<Time>,
+    mut task_runner: AsyncTaskRunner<Vec<Movement>>,
+    mut solve_runner: AsyncTaskRunner<Option<(Solution, Duration)>>,
+    mut tutorial_runner: AsyncTaskRunner<Option<StagedSolution>>,
+    mut last_layer: ResMut<LastLayerCase>,
 ) {
     egui::Window::new("Settings")
         .vscroll(true)
         .show(contexts.ctx_mut(), |ui| {
-            ui.add(egui::Slider::new(&mut settings.view_rotation_speed, 1.0..=10.0).text("view rotation speed"));
-            ui.add(egui::Slider::new(&mut settings.layer_rotation_speed, 1.0..=50.0).text("layer rotation speed"));
+            ui.add(egui::Slider::new(&mut settings.view_rotation_speed, VIEW_ROTATION_SPEEDS).text("view rotation speed"));
+            ui.add(egui::Slider::new(&mut settings.layer_rotation_speed, LAYER_ROTATION_SPEEDS).text("layer rotation speed"));
+            egui::ComboBox::from_label("rotation easing")
+                .selected_text(settings.easing.label())
+                .show_ui(ui, |ui| {
+                    for easing in Easing::ALL {
+                        ui.selectable_value(&mut settings.easing, easing, easing.label());
+                    }
+                });
+            ui.checkbox(&mut settings.overlap_parallel_moves, "overlap parallel moves");
+            ui.checkbox(&mut settings.show_net, "show net");
+
+            ui.collapsing("colours", |ui| {
+                ui.horizontal_wrapped(|ui| {
+                    for scheme in ColorScheme::ALL {
+                        if ui.add(egui::Button::new(scheme.label())).clicked() {
+                            settings.set_scheme(scheme);
+                        }
+                    }
+                });
+                let settings: &mut Settings = &mut settings;
+                for (name, color) in [
+                    ("up", &mut settings.color_up), ("down", &mut settings.color_down),
+                    ("left", &mut settings.color_left), ("right", &mut settings.color_right),
+                    ("front", &mut settings.color_front), ("back", &mut settings.color_back),
+                    ("bevel", &mut settings.color_beleved),
+                ] {
+                    ui.horizontal(|ui| {
+                        let mut rgb = color.to_srgba().to_f32_array_no_alpha();
+                        if ui.color_edit_button_rgb(&mut rgb).changed() {
+                            *color = Color::srgb(rgb[0], rgb[1], rgb[2]);
+                        }
+                        ui.label(name);
+                    });
+                }
+                if !settings.has_distinct_colors() {
+                    ui.colored_label(egui::Color32::RED, "every colour must be different");
+                }
+            });
+            ui.horizontal(|ui| {
+                if ui.add(egui::Button::new("reset view")).clicked() {
+                    orbit.reset();
+                }
+                for view in View::ALL {
+                    if ui.add(egui::Button::new(view.label())).clicked() {
+                        orbit.set_view(view);
+                    }
+                }
+            });
             if ui.add(egui::Button::new("scramble")).clicked() {
                 if status.action_queue.is_empty() && !status.computing_solution {
-                    status.action_queue.append(&mut gen_random_movements(25));
+                    let mut scramble = gen_random_movements(25, settings.layers);
+                    recorder.scramble(&puzzle, scramble.make_contiguous());
+                    status.action_queue.append(&mut scramble);
+                    tutorial.solution = None;
+                    solve_result.solution = None;
+                    playback.clear();
+                }
+            }
+
+            ui.horizontal(|ui| {
+                ui.label("rotate cube");
+                for (name, axis, inverse) in [
+                    ("x", RotateAxis::X, false), ("y", RotateAxis::Y, false), ("z", RotateAxis::Z, false),
+                    ("x'", RotateAxis::X, true), ("y'", RotateAxis::Y, true), ("z'", RotateAxis::Z, true),
+                ] {
+                    if ui.add(egui::Button::new(name)).clicked() && !status.computing_solution {
+                        let m = Movement::rotation(axis, inverse, settings.layers);
+                        status.action_queue.push_back(m);
+                        recorder.record(m, time.elapsed_seconds_f64());
+                        playback.clear();
+                    }
+                }
+            });
+            if let Ok(last_move) = notation(&status.last_moves, settings.layers) {
+                if !last_move.is_empty() {
+                    ui.label(format!("last move: {}", last_move));
+                }
+            }
+
+            // the solvers and the last layer recognition only know the 3x3
+            let solvable = puzzle.0.layers() == 3;
+            if ui.add_enabled(solvable, egui::Button::new("solve")).clicked() {
+                if solve_runner.is_idle() && status.action_queue.is_empty() {
+                    status.computing_solution = true;
+                    solve_result.solution = None;
+                    playback.clear();
+
+                    solve_runner.start(solve(puzzle.0.clone()));
+                }
+            }
+
+            if let Some(solution) = &solve_result.solution {
+                ui.label(format!("phase 1: {}", solution.phase1_to_string()));
+                ui.label(format!("phase 2: {}", solution.phase2_to_string()));
+                ui.label(format!("{} moves, found in {} ms", solution.len(), solve_result.solve_time.as_millis()));
+                if ui.add(egui::Button::new("copy solution")).clicked() {
+                    ui.ctx().copy_text(solution.to_string());
+                }
+            }
+
+            if ui.add_enabled(solvable, egui::Button::new("beginner tutorial")).clicked() {
+                if tutorial_runner.is_idle() && task_runner.is_idle() && status.action_queue.is_empty() {
+                    status.computing_solution = true;
+
+                    playback.clear();
+                    tutorial_runner.start(solve_beginner(puzzle.0.clone()));
+                }
+            }
+
+            if ui.add_enabled(solvable, egui::Button::new("CFOP reference")).clicked() {
+                if tutorial_runner.is_idle() && task_runner.is_idle() && status.action_queue.is_empty() {
+                    status.computing_solution = true;
+
+                    playback.clear();
+                    tutorial_runner.start(solve_cfop(puzzle.0.clone()));
                 }
             }
 
-            if ui.add(egui::Button::new("solve")).clicked() {
-                if task_runner.is_idle() {
+            if ui.add_enabled(solvable, egui::Button::new("Roux reference")).clicked() {
+                if tutorial_runner.is_idle() && task_runner.is_idle() && status.action_queue.is_empty() {
                     status.computing_solution = true;
 
-                    let cubes: Vec<Cube> = cube_info.cubes.iter().filter_map(|c| cube_query.get(*c).ok().cloned()).collect();
-                    task_runner.start(solve(cubes));
+                    playback.clear();
+                    tutorial_runner.start(solve_roux(puzzle.0.clone()));
+                }
+            }
+
+            // any movement the tutorial did not queue leaves the solution stale
+            let tutorial_idle = status.action_queue.is_empty() && status.cur_action.is_none() && !status.computing_solution;
+            if tutorial_idle && tutorial.solution.is_some() && tutorial.state.as_ref() != Some(&puzzle.0) {
+                tutorial.solution = None;
+            }
+
+            let next_stage = tutorial.next_stage;
+            if let Some(solution) = tutorial.solution.clone() {
+                ui.separator();
+                for stage in &solution.stages {
+                    ui.label(format!("{}: {} moves", stage.name, stage.moves.len()));
+                }
+                ui.label(format!("total: {} moves", solution.get_all_moves().len()));
+                if next_stage > 0 && !status.action_queue.is_empty() {
+                    ui.label(format!("playing: {}", solution.stages[next_stage - 1].name));
+                }
+                if let Some(stage) = solution.stages.get(next_stage) {
+                    ui.label(format!("stage {}/{}: {}", next_stage + 1, solution.stages.len(), stage.name));
+                    ui.label(if stage.moves.is_empty() { "(skip)".to_string() } else { stage.to_string() });
+                    if ui.add(egui::Button::new("play stage")).clicked() {
+                        if status.action_queue.is_empty() {
+                            let movements = generate_moves_from_string(stage.moves.clone());
+                            if let Some(state) = &mut tutorial.state {
+                                state.apply_all(&movements);
+                            }
+                            status.action_queue.extend(movements);
+                            tutorial.next_stage += 1;
+                        }
+                    }
+                } else {
+                    ui.label("solved!");
+                }
+            }
+
+            ui.separator();
+            egui::ComboBox::from_label("training mode")
+                .selected_text(settings.training_mode.label())
+                .show_ui(ui, |ui| {
+                    for mode in TrainingMode::ALL {
+                        ui.selectable_value(&mut settings.training_mode, mode, mode.label());
+                    }
+                });
+            if ui.add_enabled(solvable, egui::Button::new("training scramble")).clicked() {
+                // like "scramble", and no solution may arrive for the cube before the scramble
+                let runners_idle = task_runner.is_idle() && solve_runner.is_idle() && tutorial_runner.is_idle();
+                if runners_idle && status.action_queue.is_empty() && !status.computing_solution {
+                    status.computing_solution = true;
+
+                    tutorial.solution = None;
+                    solve_result.solution = None;
+                    playback.clear();
+                    task_runner.start(training_scramble(puzzle.0.clone(), settings.training_mode));
+                }
+            }
+
+            let idle = status.action_queue.is_empty() && status.cur_action.is_none() && !status.computing_solution;
+            if idle && solvable {
+                // Recognition tries every case, so it only runs again once the puzzle has changed.
+                if last_layer.0.as_ref().map_or(true, |(state, _)| *state != puzzle.0) {
+                    last_layer.0 = Some((puzzle.0.clone(), recognize_last_layer(&puzzle.0)));
+                }
+                if let Some((_, Some(found))) = &last_layer.0 {
+                    let moves = found.get_all_moves();
+                    if !moves.is_empty() {
+                        ui.separator();
+                        ui.label(format!("last layer case: {}", found.case.name));
+                        let pre_auf = found.pre_auf.map(|m| format!("{m} ")).unwrap_or_default();
+                        ui.label(format!("{pre_auf}{}", found.case.notation));
+                        if ui.add(egui::Button::new("play algorithm")).clicked() {
+                            status.action_queue.extend(generate_moves_from_string(moves));
+                            playback.clear();
+                        }
+                    }
                 }
             }
         });
@@ -43,5 +261,282 @@ pub fn update_ui(
 
         _ => ()
     }
+
+    match solve_runner.poll() {
+        AsyncTaskStatus::Finished(found) => {
+            if let Some((solution, solve_time)) = found {
+                playback.load(generate_moves_from_string(solution.get_all_moves()));
+                playback.play();
+                solve_result.solution = Some(solution);
+                solve_result.solve_time = solve_time;
+            }
+            status.computing_solution = false;
+        },
+
+        _ => ()
+    }
+
+    match tutorial_runner.poll() {
+        AsyncTaskStatus::Finished(solution) => {
+            tutorial.solution = solution;
+            tutorial.next_stage = 0;
+            tutorial.state = Some(puzzle.0.clone());
+            status.computing_solution = false;
+        },
+
+        _ => ()
+    }
 }
 
+
+/// Size of a sticker of the net, in points.
+const NET_STICKER_SIZE: f32 = 16.0;
+/// Position of each face of the net in faces, in the facelet order U, R, F, D, L, B.
+const NET_FACES: [(f32, f32); 6] = [(1.0, 0.0), (2.0, 1.0), (1.0, 1.0), (1.0, 2.0), (0.0, 1.0), (3.0, 1.0)];
+
+/// Unfolded net of the cube, as drawn at the top of `solver.rs`.
+pub fn update_net_ui(
+    mut contexts: EguiContexts,
+    settings: Res<Settings>,
+    puzzle: Res<Puzzle>,
+) {
+    if !settings.show_net {
+        return;
+    }
+
+    let n = puzzle.layers() as usize;
+
+    egui::Window::new("Net")
+        .resizable(false)
+        .show(contexts.ctx_mut(), |ui| {
+            let (response, painter) = ui.allocate_painter(egui::vec2(4.0, 3.0) * (n as f32 * NET_STICKER_SIZE), egui::Sense::hover());
+            let origin = response.rect.min;
+
+            for (i, side) in puzzle.facelets().iter().enumerate() {
+                let (face_x, face_y) = NET_FACES[i / (n * n)];
+                let x = face_x * n as f32 + (i % n) as f32;
+                let y = face_y * n as f32 + (i % (n * n) / n) as f32;
+                let rect = egui::Rect::from_min_size(
+                    origin + egui::vec2(x, y) * NET_STICKER_SIZE,
+                    egui::Vec2::splat(NET_STICKER_SIZE),
+                ).shrink(1.0);
+                let [r, g, b] = settings.side_color(*side).to_srgba().to_u8_array_no_alpha();
+                painter.rect_filled(rect, 2.0, egui::Color32::from_rgb(r, g, b));
+            }
+        });
+}
+
+/// Playback panel of the solution found by "solve" or of an imported reconstruction.
+pub fn update_playback_ui(
+    mut contexts: EguiContexts,
+    mut playback: ResMut<Playback>,
+    settings: Res<Settings>,
+) {
+    if !playback.is_loaded() {
+        return;
+    }
+
+    egui::Window::new("Playback")
+        .show(contexts.ctx_mut(), |ui| {
+            ui.horizontal(|ui| {
+                if ui.add(egui::Button::new("step back")).clicked() {
+                    playback.step_back();
+                }
+                if playback.is_playing() {
+                    if ui.add(egui::Button::new("pause")).clicked() {
+                        playback.pause();
+                    }
+                } else if ui.add(egui::Button::new("play")).clicked() {
+                    playback.play();
+                }
+                if ui.add(egui::Button::new("step forward")).clicked() {
+                    playback.step_forward();
+                }
+                if ui.add(egui::Button::new("close")).clicked() {
+                    playback.clear();
+                }
+            });
+
+            let len = playback.moves.len();
+            let mut target = playback.target;
+            if ui.add(egui::Slider::new(&mut target, 0..=len).text("move")).changed() {
+                playback.seek(target);
+            }
+            ui.add(egui::Slider::new(&mut playback.speed, 1.0..=50.0).text("playback speed"));
+
+            ui.horizontal_wrapped(|ui| {
+                let position = playback.position;
+                for (i, m) in playback.moves.clone().iter().enumerate() {
+                    let label = movement_notation(m, settings.layers).unwrap_or_else(|_| "?".to_string());
+                    if ui.selectable_label(i + 1 == position, label).clicked() {
+                        playback.seek(i + 1);
+                    }
+                }
+            });
+        });
+}
+
+/// Panel of the recorded solve, exporting it as a reconstruction and importing one for playback.
+pub fn update_recorder_ui(
+    mut contexts: EguiContexts,
+    mut recorder: ResMut<Recorder>,
+    mut status: ResMut<ActionStatus>,
+    mut playback: ResMut<Playback>,
+    mut text: Local<String>,
+    mut error: Local<Option<&'static str>>,
+    mut splits: Local<Option<(Reconstruction, SolveMethod, Vec<Split>)>>,
+    mut pending: Local<Option<Vec<Movement>>>,
+    puzzle: Res<Puzzle>,
+    mut settings: ResMut<Settings>,
+    mut task_runner: AsyncTaskRunner<Vec<Movement>>,
+) {
+    let layers = settings.layers;
+
+    egui::Window::new("Reconstruction")
+        .default_open(false)
+        .show(contexts.ctx_mut(), |ui| {
+            let recorded = !recorder.recording && !recorder.reconstruction.moves.is_empty();
+            let reconstruction = &recorder.reconstruction;
+            if recorder.recording {
+                ui.label(format!("recording: {} moves", reconstruction.moves.len()));
+            } else if !recorded {
+                ui.label("scramble a solved cube to record a solve");
+            } else {
+                ui.label(format!(
+                    "{:.2}s, {} moves, {:.2} TPS",
+                    reconstruction.duration(), reconstruction.moves.len(), reconstruction.tps(),
+                ));
+                egui::ComboBox::from_label("method")
+                    .selected_text(settings.solve_method.label())
+                    .show_ui(ui, |ui| {
+                        for method in SolveMethod::ALL {
+                            ui.selectable_value(&mut settings.solve_method, method, method.label());
+                        }
+                    });
+                // The splits replay the whole solve, so they only change with the solve or the method.
+                let method = settings.solve_method;
+                let stale = splits.as_ref().map_or(true, |(cached, cached_method, _)| {
+                    cached != reconstruction || *cached_method != method
+                });
+                if stale {
+                    *splits = Some((reconstruction.clone(), method, reconstruction.splits(layers, method)));
+                }
+                for split in splits.as_ref().map_or(&[][..], |(_, _, splits)| splits) {
+                    ui.label(format!("{}: {:.2}s, {} moves", split.name, split.time, split.moves));
+                }
+            }
+
+            let idle = task_runner.is_idle() && status.action_queue.is_empty() && !status.computing_solution;
+            ui.horizontal(|ui| {
+                if ui.add_enabled(recorded, egui::Button::new("export")).clicked() {
+                    match recorder.reconstruction.export(layers, settings.solve_method) {
+                        Ok(exported) => {
+                            *text = exported;
+                            ui.ctx().copy_text(text.clone());
+                            *error = None;
+                        },
+                        Err(_) => *error = Some("the solve does not fit the cube"),
+                    }
+                }
+                if ui.add_enabled(idle, egui::Button::new("import")).clicked() {
+                    *error = None;
+                    match Reconstruction::import(&text, layers) {
+                        None => *error = Some("invalid reconstruction"),
+                        Some(_) if layers != 3 && !puzzle.is_solved() => *error = Some("solve the cube first"),
+                        Some(imported) => {
+                            // the cube is scrambled first, the solve is played back once it is
+                            status.computing_solution = true;
+                            playback.clear();
+                            *pending = Some(imported.movements());
+                            task_runner.start(reconstruction_scramble(puzzle.0.clone(), imported.scramble.clone()));
+                            recorder.recording = false;
+                            recorder.reconstruction = imported;
+                        },
+                    }
+                }
+            });
+            if let Some(error) = *error {
+                ui.colored_label(egui::Color32::RED, error);
+            }
+            ui.add(egui::TextEdit::multiline(&mut *text).desired_rows(4));
+        });
+
+    match task_runner.poll() {
+        AsyncTaskStatus::Finished(res) => {
+            status.action_queue.extend(res);
+            status.computing_solution = false;
+            if let Some(movements) = pending.take() {
+                playback.load(movements);
+            }
+        },
+
+        _ => ()
+    }
+}
+
+/// Panel saving the session, and loading it back to continue where the user left off.
+pub fn update_session_ui(
+    mut contexts: EguiContexts,
+    mut settings: ResMut<Settings>,
+    mut puzzle: ResMut<Puzzle>,
+    mut status: ResMut<ActionStatus>,
+    mut recorder: ResMut<Recorder>,
+    mut playback: ResMut<Playback>,
+    mut tutorial: ResMut<Tutorial>,
+    mut solve_result: ResMut<SolveResult>,
+    mut last_layer: ResMut<LastLayerCase>,
+    mut cubes: Query<(&mut Cube, &mut Transform, &Handle<Mesh>)>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut message: Local<Option<String>>,
+) {
+    egui::Window::new("Session")
+        .default_open(false)
+        .show(contexts.ctx_mut(), |ui| {
+            if let Some(best) = recorder.best() {
+                ui.label(format!(
+                    "{} solves, best {:.2}s, mean {:.2}s",
+                    recorder.solves.len(), best, recorder.mean().unwrap_or_default(),
+                ));
+            }
+
+            let idle = status.action_queue.is_empty() && status.cur_action.is_none() && !status.computing_solution;
+            ui.horizontal(|ui| {
+                if ui.add(egui::Button::new("save")).clicked() {
+                    let session = Session {
+                        version: SESSION_VERSION,
+                        puzzle: puzzle.0.clone(),
+                        history: status.history.clone(),
+                        settings: settings.clone(),
+                        recorder: recorder.clone(),
+                    };
+                    *message = Some(match session.save() {
+                        Ok(()) => "session saved".to_string(),
+                        Err(e) => e.to_string(),
+                    });
+                }
+                if ui.add_enabled(idle, egui::Button::new("load")).clicked() {
+                    match Session::load(settings.layers) {
+                        Ok(session) => {
+                            puzzle.0 = session.puzzle;
+                            // the meshes get the current colours, `recolor_cubes` then changes them to the loaded ones
+                            reset_cubes(&puzzle, &settings, &mut cubes, &mut meshes);
+                            *settings = Settings { layers: settings.layers, ..session.settings };
+                            *recorder = session.recorder;
+                            status.history = session.history;
+                            status.last_moves.clear();
+                            // nothing found for the previous puzzle applies to the loaded one
+                            playback.clear();
+                            *tutorial = Tutorial::default();
+                            *solve_result = SolveResult::default();
+                            last_layer.0 = None;
+                            *message = Some("session loaded".to_string());
+                        },
+                        Err(e) => *message = Some(e.to_string()),
+                    }
+                }
+            });
+            if let Some(message) = &*message {
+                ui.label(message);
+            }
+        });
+}